@@ -0,0 +1,119 @@
+//! Wall-clock time of a request fanning out to many nested requests,
+//! compared with sending the same requests one after the other.
+//!
+//! A local server answering every request after a fixed delay is used,
+//! so the results don't depend on the network. Run with `cargo bench`.
+
+use gluerunner::{heap, Runner};
+use reqwest::Client;
+use std::time::{Duration, Instant};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{TcpListener, TcpStream},
+	time::sleep,
+};
+
+/// Time taken by the server to answer each request.
+const DELAY: Duration = Duration::from_millis(100);
+
+/// Numbers of nested requests benchmarked.
+const FAN_OUT: [usize; 4] = [1, 4, 16, 64];
+
+/// Times each benchmark is repeated, keeping the best run.
+const RUNS: usize = 5;
+
+#[tokio::main]
+async fn main() {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let host = format!("http://{}", listener.local_addr().unwrap());
+
+	tokio::spawn(async move {
+		while let Ok((stream, _)) = listener.accept().await {
+			tokio::spawn(respond(stream));
+		}
+	});
+
+	let client = Client::new();
+
+	println!(
+		"{:>8} {:>14} {:>14} {:>8}",
+		"nested", "sequential", "concurrent", "gain"
+	);
+
+	for fan_out in FAN_OUT {
+		let sequential = best_of(|| sequential(&host, fan_out, &client)).await;
+		let concurrent = best_of(|| concurrent(&host, fan_out, &client)).await;
+
+		println!(
+			"{:>8} {:>12}ms {:>12}ms {:>7.1}x",
+			fan_out,
+			sequential.as_millis(),
+			concurrent.as_millis(),
+			sequential.as_secs_f64() / concurrent.as_secs_f64()
+		);
+	}
+}
+
+/// Send `fan_out` requests one by one, then the request using their results.
+async fn sequential(host: &str, fan_out: usize, client: &Client) {
+	for x in 0..fan_out {
+		run(&format!("get {host}/nested/{x}"), client).await;
+	}
+
+	run(&format!("get {host}/root"), client).await;
+}
+
+/// Send a single request with `fan_out` nested requests.
+async fn concurrent(host: &str, fan_out: usize, client: &Client) {
+	let nested: String = (0..fan_out)
+		.map(|x| format!(" ?q{x}={{get {host}/nested/{x}}}"))
+		.collect();
+
+	run(&format!("get {host}/root{nested}"), client).await;
+}
+
+/// Execute `command` in a new `Runner`.
+async fn run(command: &str, client: &Client) {
+	Runner::from_string(command, heap(), client.clone(), false)
+		.unwrap()
+		.execute()
+		.await
+		.unwrap();
+}
+
+/// Time `bench` over `RUNS` runs, returning the fastest one.
+async fn best_of<F, T>(bench: F) -> Duration
+where
+	F: Fn() -> T,
+	T: std::future::Future<Output = ()>,
+{
+	let mut best = Duration::MAX;
+
+	for _ in 0..RUNS {
+		let start = Instant::now();
+		bench().await;
+		best = best.min(start.elapsed());
+	}
+
+	best
+}
+
+/// Answer the http request read from `stream` after `DELAY`.
+async fn respond(mut stream: TcpStream) {
+	let mut request = Vec::new();
+	let mut buffer = [0; 1024];
+
+	// Requests are sent without body, so they end with an empty line.
+	while !request.ends_with(b"\r\n\r\n") {
+		match stream.read(&mut buffer).await {
+			Ok(0) | Err(_) => return,
+			Ok(x) => request.extend_from_slice(&buffer[..x]),
+		}
+	}
+
+	sleep(DELAY).await;
+
+	let _ = stream
+		.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+		.await;
+}
@@ -0,0 +1,112 @@
+use crate::http::response_body;
+use gluescript::{constants, Assertion, Comparison, GlueNode, Response};
+use jsonpath_rust::JsonPathFinder;
+use serde_json::Value;
+use std::fmt;
+
+/// Outcome of an `Assertion` checked on the response of a request.
+#[derive(Debug, Clone)]
+pub struct AssertionResult {
+	/// Request the assertion belongs to, as written by the user.
+	pub request: String,
+
+	/// The checked assertion.
+	pub assertion: Assertion,
+
+	/// Why the assertion failed, `None` if it passed.
+	pub failure: Option<String>,
+}
+
+impl AssertionResult {
+	/// Whether the assertion holds.
+	pub fn passed(&self) -> bool {
+		self.failure.is_none()
+	}
+}
+
+impl fmt::Display for AssertionResult {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match &self.failure {
+			None => write!(f, "`{}` passed", self.assertion),
+			Some(x) => write!(f, "`{}` failed: {}", self.assertion, x),
+		}
+	}
+}
+
+/// Check all assertions of `node` on its `response`, or on its `saved`
+/// result if it was read from memory.
+pub fn check_assertions(
+	node: &GlueNode,
+	response: Option<&Response>,
+	saved: &str,
+) -> Vec<AssertionResult> {
+	let body = match response {
+		None => String::from(saved),
+		Some(x) => response_body(node, x),
+	};
+
+	node.assertions
+		.iter()
+		.map(|assertion| AssertionResult {
+			request: node.command.clone(),
+			assertion: assertion.clone(),
+			failure: check(assertion, response, &body).err(),
+		})
+		.collect()
+}
+
+/// Check `assertion` on `response` and its `body`, returning why
+/// it failed as `Err`.
+fn check(assertion: &Assertion, response: Option<&Response>, body: &str) -> Result<(), String> {
+	let subject = &assertion.subject;
+	let expected = &assertion.expected;
+
+	let actual = match subject.starts_with(constants::JSONPATH_ROOT) {
+		true => select(subject, body)?,
+		false => response.and_then(|x| x.field(subject)),
+	};
+
+	let actual = match (actual, assertion.comparison) {
+		(None, _) => return Err(format!("`{}` not found", subject)),
+		(Some(_), Comparison::Exists) => return Ok(()),
+		(Some(x), _) => x,
+	};
+
+	let passed = match assertion.comparison {
+		Comparison::Equals => &actual == expected,
+		Comparison::NotEquals => &actual != expected,
+		_ => assertion
+			.pattern
+			.as_ref()
+			.is_some_and(|x| x.is_match(&actual)),
+	};
+
+	match (passed, assertion.comparison) {
+		(true, _) => Ok(()),
+		(false, Comparison::NotEquals) => Err(format!("expected anything but `{}`", expected)),
+		(false, Comparison::Matches) => Err(format!(
+			"expected to match `{}`, got `{}`",
+			expected, actual
+		)),
+		(false, _) => Err(format!("expected `{}`, got `{}`", expected, actual)),
+	}
+}
+
+/// Select the value of `selector` in `body`. Strings are taken without
+/// quotes, and multiple values are taken as a JSON array.
+///
+/// `None` is returned if nothing is selected.
+fn select(selector: &str, body: &str) -> Result<Option<String>, String> {
+	let finder = JsonPathFinder::from_str(body, selector)?;
+
+	let value = match finder.find_slice().as_slice() {
+		[] => return Ok(None),
+		[x] => (*x).clone(),
+		x => Value::Array(x.iter().map(|x| (*x).clone()).collect()),
+	};
+
+	Ok(Some(match value {
+		Value::String(x) => x,
+		x => x.to_string(),
+	}))
+}
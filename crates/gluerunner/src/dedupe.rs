@@ -0,0 +1,60 @@
+use gluescript::{GlueNode, Response};
+use reqwest::Method;
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{Arc, Mutex},
+};
+use tokio::sync::OnceCell;
+
+/// Responses of the requests sent during a run, shared by identical
+/// requests so that each of them is sent only once.
+///
+/// Clones share the same responses.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+	/// Response of each request, by request key. A cell is created by the
+	/// first of identical requests, and filled once its response is read.
+	responses: Arc<Mutex<HashMap<String, Arc<OnceCell<Response>>>>>,
+}
+
+impl ResponseCache {
+	/// Get the cell holding the response of the request with `key`,
+	/// creating an empty one if the request was never sent.
+	pub fn entry(&self, key: String) -> Arc<OnceCell<Response>> {
+		let mut responses = self.responses.lock().unwrap();
+		Arc::clone(responses.entry(key).or_default())
+	}
+}
+
+/// Key identifying the request of a resolved `node` among identical ones,
+/// made of its method, url, headers and body.
+///
+/// `None` is returned if the request must always be sent. Requests are
+/// shared when the node says so, or by default when their method is safe.
+pub fn request_key(node: &GlueNode) -> Option<String> {
+	let method = Method::from_bytes(node.method.to_uppercase().as_bytes()).ok()?;
+
+	if !node.dedupe.unwrap_or_else(|| method.is_safe()) {
+		return None;
+	}
+
+	// Headers and body values are sorted, as their order doesn't
+	// change the request.
+	let mut headers: Vec<(&str, &[u8])> = node
+		.headers
+		.iter()
+		.flatten()
+		.map(|(key, value)| (key.as_str(), value.as_bytes()))
+		.collect();
+	headers.sort();
+
+	let body = node.body.as_ref().map(|x| {
+		(
+			format!("{:?}", x.body_type),
+			x.value.iter().collect::<BTreeMap<_, _>>(),
+			&x.raw,
+		)
+	});
+
+	Some(format!("{} {} {:?} {:?}", method, node.url, headers, body))
+}
@@ -1,12 +1,135 @@
-use std::{error::Error, fmt};
+use crate::AssertionResult;
+use gluescript::ParseError;
+use reqwest::StatusCode;
+use std::{error::Error, fmt, io};
 
+/// Any failure that can happen while loading or executing gluescript.
 #[derive(Debug)]
-pub struct RequestError(pub String);
+pub enum GlueError {
+	/// Gluescript could not be parsed, or a request could not be
+	/// resolved from it.
+	Parse(ParseError),
 
-impl Error for RequestError {}
+	/// A variable was read from the heap, but nothing was saved with its name.
+	UnresolvedVariable(String),
 
-impl fmt::Display for RequestError {
+	/// The http request could not be sent, or its response could not be read.
+	Http(reqwest::Error),
+
+	/// The request written as the contained command did not complete in time.
+	Timeout(String),
+
+	/// The server answered with a status that is not considered a success.
+	Status {
+		/// Url the request was sent to.
+		url: String,
+
+		/// Response status.
+		status: StatusCode,
+
+		/// First chars of the response body.
+		excerpt: String,
+	},
+
+	/// The JSONPath selector could not be applied to the response.
+	Selector(String),
+
+	/// Some assertions on the response failed. Only failed ones are contained.
+	Assertion(Vec<AssertionResult>),
+
+	/// A script could not be read.
+	Io(io::Error),
+
+	/// The request written as the contained command was not executed, as it
+	/// uses a variable saved by a failed request.
+	Skipped {
+		/// Command of the skipped request.
+		command: String,
+
+		/// Variable saved by the failed request.
+		variable: String,
+	},
+
+	/// Some runners failed while the execution continued past errors.
+	/// All errors are contained, in order.
+	Failures(Vec<GlueError>),
+}
+
+impl GlueError {
+	/// Render the error for the user. Parse errors are rendered along with
+	/// the offending source line.
+	pub fn diagnostic(&self) -> String {
+		match self {
+			GlueError::Parse(x) => x.diagnostic(),
+			GlueError::Failures(x) => x
+				.iter()
+				.map(GlueError::diagnostic)
+				.collect::<Vec<_>>()
+				.join("\n"),
+			x => x.to_string(),
+		}
+	}
+}
+
+impl Error for GlueError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			GlueError::Parse(x) => Some(x),
+			GlueError::Http(x) => Some(x),
+			GlueError::Io(x) => Some(x),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for GlueError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "There is an error: {}", self.0)
+		match self {
+			GlueError::Parse(x) => write!(f, "{}", x),
+			GlueError::UnresolvedVariable(x) => write!(f, "Failed to resolve var `{}`", x),
+			GlueError::Http(x) => write!(f, "Http request failed: {}", x),
+			GlueError::Timeout(x) => write!(f, "Request timed out: `{}`", x),
+			GlueError::Status {
+				url,
+				status,
+				excerpt,
+			} => write!(f, "{} responded with status {}: {}", url, status, excerpt),
+			GlueError::Selector(x) => write!(f, "{}", x),
+			GlueError::Assertion(x) => {
+				let failures: Vec<String> = x
+					.iter()
+					.map(|x| format!("Assertion {} on `{}`", x, x.request))
+					.collect();
+				write!(f, "{}", failures.join("\n"))
+			}
+			GlueError::Io(x) => write!(f, "{}", x),
+			GlueError::Skipped { command, variable } => write!(
+				f,
+				"Skipped `{}`, as var `{}` was to be saved by a failed request",
+				command, variable
+			),
+			GlueError::Failures(x) => {
+				let failures: Vec<String> = x.iter().map(GlueError::to_string).collect();
+				write!(f, "{}", failures.join("\n"))
+			}
+		}
+	}
+}
+
+impl From<ParseError> for GlueError {
+	fn from(error: ParseError) -> Self {
+		GlueError::Parse(error)
+	}
+}
+
+impl From<reqwest::Error> for GlueError {
+	fn from(error: reqwest::Error) -> Self {
+		GlueError::Http(error)
+	}
+}
+
+impl From<io::Error> for GlueError {
+	fn from(error: io::Error) -> Self {
+		GlueError::Io(error)
 	}
 }
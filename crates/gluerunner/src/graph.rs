@@ -0,0 +1,63 @@
+use crate::MuxNode;
+use std::sync::Arc;
+
+/// Dependency graph of all the `GlueNode` of a `Runner`.
+///
+/// Nodes are referenced by their index in `nodes`, where the root node
+/// is always the first one. A node can be executed as soon as all of its
+/// `dependencies` have been executed.
+#[derive(Debug, Default)]
+pub struct ExecutionGraph {
+	/// All nodes of the graph, in depth-first order starting from the root.
+	pub nodes: Vec<MuxNode>,
+
+	/// Indexes of the nodes each node depends on.
+	pub dependencies: Vec<Vec<usize>>,
+
+	/// Indexes of the nodes depending on each node.
+	pub dependents: Vec<Vec<usize>>,
+}
+
+impl ExecutionGraph {
+	/// Build the graph of `root` and all its dependencies, recursively.
+	pub fn from_root(root: MuxNode) -> Self {
+		let mut graph = ExecutionGraph::default();
+		graph.add_node_recursive(root);
+		graph
+	}
+
+	/// Indexes of the nodes without dependencies, which can be executed first.
+	pub fn leaves(&self) -> Vec<usize> {
+		(0..self.nodes.len())
+			.filter(|x| self.dependencies[*x].is_empty())
+			.collect()
+	}
+
+	/// Add `node` to the graph along with all its dependencies,
+	/// returning its index.
+	fn add_node_recursive(&mut self, node: MuxNode) -> usize {
+		let index = self.nodes.len();
+
+		self.nodes.push(Arc::clone(&node));
+		self.dependencies.push(vec![]);
+		self.dependents.push(vec![]);
+
+		// Dependencies are cloned to release the lock before recursion.
+		// The graph is built before any execution, so nodes are never
+		// locked by someone else at this point.
+		let dependencies = node
+			.try_lock()
+			.expect("nodes are not locked before execution")
+			.dependencies
+			.clone();
+
+		for dependency in dependencies {
+			let dependency = self.add_node_recursive(dependency);
+
+			self.dependencies[index].push(dependency);
+			self.dependents[dependency].push(index);
+		}
+
+		index
+	}
+}
@@ -1,130 +1,541 @@
-use crate::{HeapMap, MuxNode, RequestError};
-use gluescript::{constants, RequestBodyType};
+use crate::{
+	check_assertions,
+	dedupe::{request_key, ResponseCache},
+	AssertionResult, GlueError, HeapMap, MuxNode, RetryPolicy, Throttle,
+};
+use colored::*;
+use futures_util::future::try_join_all;
+use gluescript::{
+	constants, GlueNode, ParseError, RequestBodyType, RequestDefaults, Response, StatusPolicy,
+};
 use jsonpath_rust::JsonPathFinder;
-use reqwest::Client;
+use reqwest::{header::CONTENT_TYPE, Client, Method, Url};
 use serde_json::Value;
-use std::{error::Error, sync::Arc};
-
-/// Executes http call for a specific `GlueNode` behind an `Arc<Mutex<T>>` using
-/// provided dependencies and heap `HashMap`.
-pub async fn execute_node(node: MuxNode, heap: HeapMap, log_info: bool) -> Result<(), String> {
-	// Acquire write lock on `GlueNode` mutex.
-	let mut w_node = node.lock().unwrap();
-
-	// Predicate can now be resolved as `GlueNode` should have all dependencies
-	// resolved.
-	match w_node.resolve_predicate() {
-		Err(x) => return Err(x),
-		_ => (),
-	};
+use std::{
+	sync::{self, Arc},
+	time::Instant,
+};
+use tokio::{sync::Mutex, time::sleep};
 
-	// If verbose mode is enabled, we print the http request that is about
-	// to be fired
-	if log_info {
-		w_node.print_info();
-	}
+/// Settings and shared state used to execute every `GlueNode` of a `Runner`.
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+	/// Shared memory to read and write saved results.
+	pub heap: HeapMap,
 
-	// Clone the method string from the node
-	let method = String::from(&w_node.method);
+	/// Http client sending all requests.
+	pub client: Client,
 
-	// Release the lock on `node` to allow http_request to run.
-	drop(w_node);
+	/// Response statuses accepted as a success, other than 2xx and 3xx.
+	pub accept: StatusPolicy,
 
-	// Get `GlueNode` result executing http request or read from the heap
-	let result = match method.as_str() {
-		// Take the result from the heap if it's a saved variable
-		constants::REQ => {
-			let r_node = node.lock().unwrap();
+	/// Retry policy of requests with idempotent methods.
+	pub retry: RetryPolicy,
 
-			String::from(
-				heap.lock()
-					.unwrap()
-					.get(&r_node.url)
-					.expect(constants::ERR_UNRESOLVED_VAR),
-			)
-		}
+	/// Base url and headers of all requests.
+	pub defaults: RequestDefaults,
 
-		// Or with other methods, an http request is fired
-		_ => match send_http_request(Arc::clone(&node)).await {
-			Err(x) => return Err(x.to_string()),
-			Ok(x) => x,
-		},
+	/// Limits on requests in flight and requests per host.
+	pub throttle: Throttle,
+
+	/// Responses shared by identical requests.
+	pub responses: ResponseCache,
+
+	/// Outcomes of all assertions checked so far, in order.
+	pub assertions: Arc<sync::Mutex<Vec<AssertionResult>>>,
+
+	/// Whether request info is printed before sending requests.
+	pub log_info: bool,
+}
+
+/// Executes http call for a specific `GlueNode` behind an async `Arc<Mutex<T>>`
+/// using the heap and settings of `context`.
+///
+/// Requests are sent within the limits of the throttle, and identical
+/// requests share the same response. Failed requests are retried as defined
+/// by the retry policy, or by the `GlueNode` itself. Responses with a status
+/// not accepted by the context, or by the `GlueNode` itself, are returned as `Err`.
+/// If the `GlueNode` has assertions, they are checked in place of the status,
+/// and `Err` is returned if any fails.
+///
+/// A `GlueNode` with a dependency nested with `each` is executed once for
+/// each element of the dependency result instead.
+pub async fn execute_node(node: MuxNode, context: ExecutionContext) -> Result<(), GlueError> {
+	let each = {
+		let r_node = node.lock().await;
+		r_node
+			.each_dependency
+			.map(|x| Arc::clone(&r_node.dependencies[x]))
 	};
 
+	if let Some(dependency) = each {
+		return execute_each(node, dependency, context).await;
+	}
+
+	let (response, saved) = fetch(&node, &context).await?;
+
 	// Lock writable node again to continue operations on it.
-	let mut w_node = node.lock().unwrap();
+	let mut w_node = node.lock().await;
+
+	// Response is kept even if assertions fail, so it can be reported.
+	w_node.response = response;
+	assert_response(&w_node, w_node.response.as_ref(), &saved, &context)?;
 
 	// The `GlueNode` is considered to be root if its depth is 0
 	let is_root = w_node.depth == 0;
 
 	// Select the response value based on the provided selector.
-	// Get the whole response if no selector is provided.
-	w_node.result = match get_response_value(&w_node.result_selector, &result, !is_root, is_root) {
-		Err(x) => return Err(x),
-		Ok(x) => x,
+	// Get the whole response if no selector is provided. Nodes nested
+	// with `each` select the whole list of values instead.
+	let result = match &w_node.response {
+		None if w_node.each => select_list(&w_node.result_selector, &saved)?,
+		None => get_response_value(&w_node.result_selector, &saved, !is_root, is_root)?,
+		Some(x) if w_node.each => select_list(&w_node.result_selector, &response_body(&w_node, x))?,
+		Some(x) => select_response_value(&w_node, x, is_root)?,
 	};
+	w_node.result = result;
 
-	// If `save_as` has a value, then `result` value is saved into heap with
-	// `save_as` as key and `result` as value.
-	// Subsequent runs will be able to reuse this result from heap.
-	if w_node.save_as.is_some() {
-		// Get key to be used from `save_as` property
-		let var_key = String::from(w_node.save_as.clone().unwrap().trim());
+	save_result(&w_node, &context.heap);
+	Ok(())
+}
+
+/// Executes `node` once for each element of the list selected by its
+/// `dependency` nested with `each`. All executions run concurrently,
+/// within the limits of the throttle.
+///
+/// The result of `node` is the list of the results of all executions,
+/// in the same order of the elements.
+async fn execute_each(
+	node: MuxNode,
+	dependency: MuxNode,
+	context: ExecutionContext,
+) -> Result<(), GlueError> {
+	let list = dependency.lock().await.result.clone();
+	let elements: Vec<Value> =
+		serde_json::from_str(&list).map_err(|x| GlueError::Selector(x.to_string()))?;
+
+	let template = node.lock().await.clone();
+	let context = &context;
+
+	let executions = elements.iter().map(|element| {
+		// String elements are used without quotes, like other results.
+		let element = match element {
+			Value::String(x) => x.clone(),
+			x => x.to_string(),
+		};
+
+		let item: MuxNode = Arc::new(Mutex::new(template.resolve_each(&element)));
+
+		async move {
+			let (response, saved) = fetch(&item, context).await?;
+			let item = item.lock().await;
+
+			assert_response(&item, response.as_ref(), &saved, context)?;
+			select_item(&item, response.as_ref(), &saved)
+		}
+	});
 
-		// Acquire mutable lock on heap, as it might be shared by other `Runner` too.
-		let mut heap = heap.lock().unwrap();
+	let results = try_join_all(executions).await?;
 
-		heap.insert(var_key, String::from(&w_node.result));
+	let mut w_node = node.lock().await;
 
-		// Release immediately the lock to avoid useless blocks for
-		// other threads.
-		drop(heap);
+	w_node.result = match w_node.depth {
+		0 => serde_json::to_string_pretty(&results),
+		_ => serde_json::to_string(&results),
 	}
+	.map_err(|x| GlueError::Selector(x.to_string()))?;
+
+	// The node itself is never resolved, so the name of its
+	// variable is resolved apart.
+	w_node.resolve_save_as();
+	save_result(&w_node, &context.heap);
 
 	Ok(())
 }
 
-/// Executes HTTP request declared in `node`.
+/// Get the response of `node`, executing its http request, or its saved
+/// result from the heap of `context` if it reads a variable.
 ///
-/// `node` must be already full resolved.
-pub async fn send_http_request(node: MuxNode) -> Result<String, Box<dyn Error>> {
-	// Acquire read lock on `GlueNode` mutex.
-	let node = node.lock().unwrap();
-
-	// Build request starting from requested method.
-	// Fail if method is unrecognized.
-	let client = match node.method.as_str() {
-		constants::GET => Client::new().get(&node.url),
-		constants::POST => Client::new().post(&node.url),
-		constants::PUT => Client::new().put(&node.url),
-		constants::PATCH => Client::new().patch(&node.url),
-		constants::DELETE => Client::new().delete(&node.url),
-		_ => {
-			return Err(Box::new(RequestError(
-				constants::ERR_UNKNOWN_METHOD.to_string(),
-			)))
+/// Responses with a status not accepted by `context`, or by `node`
+/// itself, are returned as `Err`, unless `node` has assertions.
+async fn fetch(
+	node: &MuxNode,
+	context: &ExecutionContext,
+) -> Result<(Option<Response>, String), GlueError> {
+	// The write lock on `GlueNode` mutex is held only in this scope, and
+	// released before the http request is fired.
+	let (method, host, key, retry) = {
+		let mut w_node = node.lock().await;
+
+		// Predicate can now be resolved as `GlueNode` should have all dependencies
+		// resolved. Defaults are applied before anything uses the request.
+		w_node.resolve_predicate()?;
+		w_node.apply_defaults(&context.defaults)?;
+
+		// If verbose mode is enabled, we print the http request that is about
+		// to be fired
+		if context.log_info {
+			w_node.print_info();
 		}
+
+		// Clone the method string and the host from the node, along
+		// with its key among identical requests and its retry policy
+		(
+			String::from(&w_node.method),
+			Url::parse(&w_node.url)
+				.ok()
+				.and_then(|x| x.host_str().map(String::from)),
+			request_key(&w_node),
+			node_retry_policy(&w_node, context.retry.clone()),
+		)
+	};
+
+	// Take the result from the heap if it's a saved variable
+	if method == constants::REQ {
+		let r_node = node.lock().await;
+
+		return match context.heap.lock().unwrap().get(&r_node.url) {
+			None => Err(GlueError::UnresolvedVariable(r_node.url.clone())),
+			Some(x) => Ok((None, String::from(x))),
+		};
+	}
+
+	// Variables assigned with `let` are resolved like nested requests,
+	// so their result is their url.
+	if method == constants::LET {
+		return Ok((None, node.lock().await.url.clone()));
+	}
+
+	// Or with other methods, an http request is fired
+	let send = || send_with_retries(node, host.as_deref(), &retry, context);
+
+	// Identical requests wait for the first one to be sent,
+	// then share its response.
+	let response = match key {
+		None => send().await?,
+		Some(x) => context
+			.responses
+			.entry(x)
+			.get_or_try_init(send)
+			.await?
+			.clone(),
+	};
+
+	// Failed responses must not be used as results, nor be fed
+	// into dependent nodes. Requests with assertions are only checked
+	// by their assertions.
+	let (accept, has_assertions) = {
+		let r_node = node.lock().await;
+		(
+			context.accept.merge(&r_node.accept),
+			!r_node.assertions.is_empty(),
+		)
 	};
 
-	// Append `GlueNode` body to request body in json or form
-	// mode.
-	let mut request = match &node.body {
-		None => client,
-		Some(body_map) => match body_map.body_type {
-			RequestBodyType::JSON => client.json(&body_map.value),
-			RequestBodyType::FORM => client.form(&body_map.value),
-			_ => client.json::<Value>(&serde_json::from_str(&body_map.raw)?),
+	if !has_assertions && !accept.accepts(response.status) {
+		return Err(GlueError::Status {
+			url: response.url.clone(),
+			status: response.status,
+			excerpt: excerpt(&response.body),
+		});
+	}
+
+	Ok((Some(response), String::new()))
+}
+
+/// Check the assertions of `node` on its `response`, or on its `saved`
+/// result, logging their outcomes in `context`.
+///
+/// `Err` is returned with the failed assertions, if any.
+fn assert_response(
+	node: &GlueNode,
+	response: Option<&Response>,
+	saved: &str,
+	context: &ExecutionContext,
+) -> Result<(), GlueError> {
+	let results = check_assertions(node, response, saved);
+
+	let failures: Vec<AssertionResult> = results.iter().filter(|x| !x.passed()).cloned().collect();
+	context.assertions.lock().unwrap().extend(results);
+
+	match failures.is_empty() {
+		true => Ok(()),
+		false => Err(GlueError::Assertion(failures)),
+	}
+}
+
+/// Save the result of `node` into `heap`, if it has to be
+/// saved as a variable.
+///
+/// Subsequent runs will be able to reuse this result from heap.
+fn save_result(node: &GlueNode, heap: &HeapMap) {
+	// If `save_as` has a value, then `result` value is saved into heap with
+	// `save_as` as key and `result` as value.
+	if let Some(name) = &node.save_as {
+		// Acquire mutable lock on heap, as it might be shared by other
+		// `Runner` too, and release it immediately.
+		heap.lock()
+			.unwrap()
+			.insert(String::from(name.trim()), String::from(&node.result));
+	}
+}
+
+/// Get the retry policy of `node`, starting from the runner `policy`.
+///
+/// Runner retries only apply to idempotent methods, while retries
+/// set on the node itself apply to any method.
+fn node_retry_policy(node: &GlueNode, policy: RetryPolicy) -> RetryPolicy {
+	let is_idempotent = Method::from_bytes(node.method.to_uppercase().as_bytes())
+		.map(|x| x.is_idempotent())
+		.unwrap_or(false);
+
+	RetryPolicy {
+		retries: match node.retries {
+			Some(x) => x,
+			None if is_idempotent => policy.retries,
+			None => 0,
 		},
+		statuses: node.retry_on.clone().unwrap_or(policy.statuses),
+	}
+}
+
+/// Executes HTTP request declared in `node` to `host`, sending it again on
+/// failures as long as `retry` allows it.
+///
+/// Every attempt waits to be allowed by the throttle of `context`.
+async fn send_with_retries(
+	node: &MuxNode,
+	host: Option<&str>,
+	retry: &RetryPolicy,
+	context: &ExecutionContext,
+) -> Result<Response, GlueError> {
+	let mut attempt = 0;
+
+	loop {
+		// The permit is released as soon as the response is read, before
+		// waiting for the next attempt.
+		let permit = context.throttle.acquire(host).await;
+		let result = send_http_request(&context.client, Arc::clone(node)).await;
+		drop(permit);
+
+		attempt += 1;
+
+		// Give up on success, on final failures or when there are no
+		// retries left. Retried responses are checked against
+		// accepted statuses by the caller.
+		let should_retry = attempt <= retry.retries
+			&& match &result {
+				Ok(x) => retry.retries_response(x),
+				Err(x) => retry.retries_error(x),
+			};
+
+		if !should_retry {
+			return result;
+		}
+
+		let delay = retry.delay(attempt, result.as_ref().ok());
+
+		if context.log_info {
+			let reason = match &result {
+				Ok(x) => x.status.to_string(),
+				Err(x) => x.to_string(),
+			};
+
+			println!(
+				"{}",
+				format!(
+					"> retry {}/{} in {}ms: {}",
+					attempt,
+					retry.retries,
+					delay.as_millis(),
+					reason
+				)
+				.truecolor(110, 110, 110)
+			);
+		}
+
+		sleep(delay).await;
+	}
+}
+
+/// Executes HTTP request declared in `node` with `client`.
+///
+/// `node` must be already full resolved.
+pub async fn send_http_request(client: &Client, node: MuxNode) -> Result<Response, GlueError> {
+	// The read lock on `GlueNode` mutex is held only while building
+	// the request, and released before sending it.
+	let (request, command) = {
+		let node = node.lock().await;
+
+		// Build request starting from requested method.
+		// Keywords and custom methods are both sent uppercase.
+		// Fail if method is not a valid http method.
+		let method = match Method::from_bytes(node.method.to_uppercase().as_bytes()) {
+			Err(_) => {
+				return Err(GlueError::Parse(ParseError::new(
+					constants::ERR_UNKNOWN_METHOD,
+					node.method_span(),
+					&node.source,
+				)))
+			}
+			Ok(x) => x,
+		};
+
+		let client = client.request(method, &node.url);
+
+		// Append `GlueNode` body to request body in json or form
+		// mode.
+		let mut request = match &node.body {
+			None => client,
+			Some(body_map) => match body_map.body_type {
+				RequestBodyType::JSON => client.json(&body_map.value),
+				RequestBodyType::FORM => client.form(&body_map.value),
+				// Raw json has already been validated while resolving the node.
+				_ => client
+					.header(CONTENT_TYPE, "application/json")
+					.body(body_map.raw.clone()),
+			},
+		};
+
+		// Append `GlueNode` headers to request headers.
+		if node.headers.is_some() {
+			request = request.headers(node.headers.clone().unwrap());
+		}
+
+		// The request timeout overrides the client one.
+		if let Some(timeout) = node.timeout {
+			request = request.timeout(timeout);
+		}
+
+		(request, node.command.clone())
+	};
+
+	// Expired timeouts name the request, as they may happen while
+	// reading the body too.
+	let error = |x: reqwest::Error| match x.is_timeout() {
+		true => GlueError::Timeout(command.clone()),
+		false => GlueError::Http(x),
 	};
 
-	// Append `GlueNode` headers to request headers.
-	if node.headers.is_some() {
-		request = request.headers(node.headers.clone().unwrap());
+	// Fire http request, timing it till the whole body is read
+	let start = Instant::now();
+	let response = request.send().await.map_err(error)?;
+
+	// Everything but the body is taken before consuming the response.
+	let status = response.status();
+	let version = response.version();
+	let headers = response.headers().clone();
+	let url = response.url().to_string();
+	let body = response.text().await.map_err(error)?;
+
+	Ok(Response {
+		status,
+		version,
+		headers,
+		body,
+		duration: start.elapsed(),
+		url,
+	})
+}
+
+/// Get the first chars of a response `body`, to be shown in errors.
+fn excerpt(body: &str) -> String {
+	let body = body.trim();
+
+	match body.char_indices().nth(constants::BODY_EXCERPT_LENGTH) {
+		None => String::from(body),
+		Some((x, _)) => format!("{}...", &body[..x]),
+	}
+}
+
+/// Select the result of `node` from its http `response`.
+///
+/// JSONPath selectors are applied to the body, any other selector
+/// must be the name of a response field, like `status` or `headers.location`.
+fn select_response_value(
+	node: &GlueNode,
+	response: &Response,
+	is_root: bool,
+) -> Result<String, GlueError> {
+	let selector = &node.result_selector;
+
+	if selector.is_empty() || selector.starts_with(constants::JSONPATH_ROOT) {
+		return get_response_value(selector, &response_body(node, response), !is_root, is_root);
 	}
 
-	// Fire http request
-	let response = request.send().await?.text().await?;
-	Ok(response)
+	response.field(selector).ok_or_else(|| {
+		GlueError::Selector(format!(
+			"{} `{}`",
+			constants::ERR_UNKNOWN_RESPONSE_FIELD,
+			selector
+		))
+	})
+}
+
+/// Get the body of `response` to select the result of `node` from.
+pub(crate) fn response_body(node: &GlueNode, response: &Response) -> String {
+	// HEAD responses have no body, so headers are used in place of it.
+	match node.method.eq_ignore_ascii_case(constants::HEAD) {
+		true => response.headers_json(),
+		false => response.body.clone(),
+	}
+}
+
+/// Select the list of all values matched by `selector` in `body`, as a
+/// JSON array, for a request nested with `each`. The whole body is used if
+/// no selector is provided, and a single selected array is used as it is.
+fn select_list(selector: &String, body: &String) -> Result<String, GlueError> {
+	let list = match selector.is_empty() {
+		true => body.clone(),
+		false => get_response_value(selector, body, false, false)?,
+	};
+
+	match serde_json::from_str(&list) {
+		Ok(Value::Array(x)) => match x.as_slice() {
+			[Value::Array(x)] if !selector.is_empty() => Ok(Value::Array(x.clone()).to_string()),
+			_ => Ok(list),
+		},
+		_ => Err(GlueError::Selector(format!(
+			"{}, got: \n{}",
+			constants::ERR_EACH_NOT_LIST,
+			excerpt(&list)
+		))),
+	}
+}
+
+/// Select the result of a single execution of a request running for each
+/// element of a list, as a JSON value to be collected with the others.
+fn select_item(
+	node: &GlueNode,
+	response: Option<&Response>,
+	saved: &str,
+) -> Result<Value, GlueError> {
+	let selector = &node.result_selector;
+
+	// JSONPath selections are lists, unwrapped when holding a single value.
+	if selector.starts_with(constants::JSONPATH_ROOT) {
+		let body = match response {
+			None => String::from(saved),
+			Some(x) => response_body(node, x),
+		};
+
+		let values: Vec<Value> =
+			serde_json::from_str(&get_response_value(selector, &body, false, false)?)
+				.map_err(|x| GlueError::Selector(x.to_string()))?;
+
+		return Ok(match <[Value; 1]>::try_from(values) {
+			Ok([x]) => x,
+			Err(x) => Value::Array(x),
+		});
+	}
+
+	let result = match response {
+		None => String::from(saved),
+		Some(x) => select_response_value(node, x, false)?,
+	};
+
+	// Results which are not JSON are collected as strings.
+	Ok(serde_json::from_str(&result).unwrap_or(Value::String(result)))
 }
 
 /// Select JSON response value with a JSONPath selector
@@ -133,41 +544,37 @@ fn get_response_value(
 	response: &String,
 	just_first_slice_value: bool,
 	pretty: bool,
-) -> Result<String, String> {
+) -> Result<String, GlueError> {
 	// No path provided so we return response
 	// as is
-	if path.len() == 0 {
+	if path.is_empty() {
 		return Ok(String::from(response));
 	}
 
 	// Path has been provided so we suppose to have
 	// a json response
-	let json_selectable = match JsonPathFinder::from_str(&response[..], &path[..]) {
-		Err(x) => return Err(x),
-		Ok(x) => x,
-	};
+	let json_selectable =
+		JsonPathFinder::from_str(&response[..], &path[..]).map_err(GlueError::Selector)?;
 
 	// JSONPath returns an array with results as default
 	// but sometimes what we need from response
-	// is just a single string value
+	// is just a single value. Strings are used without
+	// quotes, like elements of lists nested with `each`.
 	if just_first_slice_value {
-		return match json_selectable.find_slice().to_vec()[0].as_str() {
-			None => Err(format!("Could not select value to use with this selector: \n{path} \non this response: \n{response}")),
-			Some(x) => Ok(String::from(x)),
+		return match json_selectable.find_slice().first() {
+			None => Err(GlueError::Selector(format!("Could not select value to use with this selector: \n{path} \non this response: \n{response}"))),
+			Some(Value::String(x)) => Ok(x.clone()),
+			Some(x) => Ok(x.to_string()),
 		};
 	}
 
 	// return prettified result string
 	if pretty {
-		return match serde_json::to_string_pretty(json_selectable.find_slice().as_slice()) {
-			Err(x) => Err(x.to_string()),
-			Ok(x) => Ok(x),
-		};
+		return serde_json::to_string_pretty(json_selectable.find_slice().as_slice())
+			.map_err(|x| GlueError::Selector(x.to_string()));
 	}
 
 	// ..or non-prettified
-	match serde_json::to_string(json_selectable.find_slice().as_slice()) {
-		Err(x) => Err(x.to_string()),
-		Ok(x) => Ok(x),
-	}
+	serde_json::to_string(json_selectable.find_slice().as_slice())
+		.map_err(|x| GlueError::Selector(x.to_string()))
 }
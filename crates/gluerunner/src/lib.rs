@@ -4,14 +4,32 @@ pub use utils::heap;
 mod runner;
 pub use runner::Runner;
 
+mod graph;
+pub use graph::ExecutionGraph;
+
 mod stack;
-pub use stack::Stack;
+pub use stack::{ErrorPolicy, Stack, TestResult};
 
 mod http;
-pub use http::{execute_node, send_http_request};
+pub use http::{execute_node, send_http_request, ExecutionContext};
+
+mod retry;
+pub use retry::RetryPolicy;
+
+mod throttle;
+pub use throttle::Throttle;
+
+mod dedupe;
+pub use dedupe::ResponseCache;
+
+mod reporter;
+pub use reporter::{one_line, JUnitReporter, Reporter, SharedReporter, TapReporter};
+
+mod assertion;
+pub use assertion::{check_assertions, AssertionResult};
 
 mod errors;
-pub use errors::RequestError;
+pub use errors::GlueError;
 
 use gluescript::GlueNode;
 use std::{
@@ -19,8 +37,6 @@ use std::{
 	sync::{Arc, Mutex},
 };
 
-type MuxNode = Arc<Mutex<GlueNode>>;
+type MuxNode = Arc<tokio::sync::Mutex<GlueNode>>;
 type HeapMap = LockedMap<String, String>;
 type LockedMap<K, V> = Arc<Mutex<HashMap<K, V>>>;
-type ParallelExecutionLayer = Vec<MuxNode>;
-type ExecutionStack = Vec<ParallelExecutionLayer>;
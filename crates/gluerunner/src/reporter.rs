@@ -0,0 +1,304 @@
+use crate::{GlueError, TestResult};
+use std::{
+	fmt::Write as _,
+	io::{self, Write},
+	sync::{Arc, Mutex},
+};
+
+/// A `Reporter` shared by all the `Stack` instances it records.
+pub type SharedReporter = Arc<Mutex<dyn Reporter>>;
+
+/// A recorder of executed `Runner` instances, writing a report of them
+/// in some format once all are executed.
+///
+/// Reporters are added to a `Stack`, which records every executed `Runner`
+/// in all of them:
+/// ```rust
+/// # use gluerunner::{JUnitReporter, Stack};
+/// # use std::{io, sync::{Arc, Mutex}};
+/// let reporter = Arc::new(Mutex::new(JUnitReporter::new(Box::new(io::sink()))));
+///
+/// let mut stack = Stack::new();
+/// stack.add_reporter(reporter.clone());
+/// ```
+pub trait Reporter: Send {
+	/// Record the outcome of a `Runner` of the suite called `suite`.
+	fn record(&mut self, suite: &str, result: &TestResult);
+
+	/// Write the report of all recorded outcomes.
+	fn finish(&mut self) -> io::Result<()>;
+}
+
+/// A `Reporter` writing JUnit XML, with a test suite for each `Stack`
+/// and a test case for each `Runner`.
+///
+/// Failed assertions are reported as failures, any other error as an error.
+pub struct JUnitReporter {
+	/// Where the report is written.
+	out: Box<dyn Write + Send>,
+
+	/// Recorded suites, in recording order.
+	suites: Vec<Suite>,
+}
+
+/// A JUnit test suite, with its test cases already rendered.
+struct Suite {
+	name: String,
+	tests: usize,
+	failures: usize,
+	errors: usize,
+	seconds: f64,
+	cases: String,
+}
+
+impl JUnitReporter {
+	/// Create a new `JUnitReporter` writing to `out`.
+	pub fn new(out: Box<dyn Write + Send>) -> Self {
+		JUnitReporter {
+			out,
+			suites: vec![],
+		}
+	}
+}
+
+impl Reporter for JUnitReporter {
+	fn record(&mut self, suite: &str, result: &TestResult) {
+		// Consecutive results of the same suite are grouped together.
+		if self.suites.last().map(|x| x.name.as_str()) != Some(suite) {
+			self.suites.push(Suite {
+				name: String::from(suite),
+				tests: 0,
+				failures: 0,
+				errors: 0,
+				seconds: 0.0,
+				cases: String::new(),
+			});
+		}
+
+		let current = self.suites.last_mut().unwrap();
+		current.tests += 1;
+		current.seconds += result.duration.as_secs_f64();
+
+		let cases = &mut current.cases;
+		let _ = writeln!(
+			cases,
+			r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+			escape_xml(&one_line(&result.request)),
+			escape_xml(suite),
+			result.duration.as_secs_f64(),
+		);
+
+		let element = match &result.error {
+			None => None,
+			Some(GlueError::Assertion(_)) => {
+				current.failures += 1;
+				Some("failure")
+			}
+			Some(_) => {
+				current.errors += 1;
+				Some("error")
+			}
+		};
+
+		if let (Some(element), Some(error)) = (element, &result.error) {
+			let message = error.to_string();
+
+			let _ = writeln!(
+				cases,
+				r#"      <{} message="{}">{}</{}>"#,
+				element,
+				escape_xml(message.lines().next().unwrap_or_default()),
+				escape_xml(&message),
+				element,
+			);
+		}
+
+		// The status and the outcome of each assertion are written
+		// as the output of the test case, one per line.
+		let output: Vec<String> = result
+			.status
+			.map(|x| format!("status {}", x))
+			.into_iter()
+			.chain(result.assertions.iter().map(|x| x.to_string()))
+			.collect();
+
+		if !output.is_empty() {
+			let _ = writeln!(
+				cases,
+				"      <system-out>{}</system-out>",
+				escape_xml(&output.join("\n"))
+			);
+		}
+
+		let _ = writeln!(cases, "    </testcase>");
+	}
+
+	fn finish(&mut self) -> io::Result<()> {
+		let suites = &self.suites;
+		let out = &mut self.out;
+
+		writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+		writeln!(
+			out,
+			r#"<testsuites name="glue" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+			suites.iter().map(|x| x.tests).sum::<usize>(),
+			suites.iter().map(|x| x.failures).sum::<usize>(),
+			suites.iter().map(|x| x.errors).sum::<usize>(),
+			// An empty sum of floats is `-0.0`, so it's started from `0.0`.
+			suites.iter().fold(0.0, |sum, x| sum + x.seconds),
+		)?;
+
+		for suite in suites {
+			writeln!(
+				out,
+				r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+				escape_xml(&suite.name),
+				suite.tests,
+				suite.failures,
+				suite.errors,
+				suite.seconds,
+			)?;
+			write!(out, "{}", suite.cases)?;
+			writeln!(out, "  </testsuite>")?;
+		}
+
+		writeln!(out, "</testsuites>")?;
+		out.flush()
+	}
+}
+
+/// A `Reporter` writing TAP version 13, with a test point for each `Runner`.
+///
+/// Each test point is followed by a YAML block with its status, duration and,
+/// if it failed, the error. Assertions are written as a subtest of their
+/// test point, and suites as comments.
+pub struct TapReporter {
+	/// Where the report is written.
+	out: Box<dyn Write + Send>,
+
+	/// Number of recorded test points.
+	count: usize,
+
+	/// Suite of the last recorded test point.
+	suite: Option<String>,
+
+	/// Test points rendered so far, written after the plan.
+	points: String,
+}
+
+impl TapReporter {
+	/// Create a new `TapReporter` writing to `out`.
+	pub fn new(out: Box<dyn Write + Send>) -> Self {
+		TapReporter {
+			out,
+			count: 0,
+			suite: None,
+			points: String::new(),
+		}
+	}
+}
+
+impl Reporter for TapReporter {
+	fn record(&mut self, suite: &str, result: &TestResult) {
+		let points = &mut self.points;
+		self.count += 1;
+
+		// A comment is written every time the suite changes.
+		if self.suite.as_deref() != Some(suite) {
+			let _ = writeln!(points, "# {}", suite);
+			self.suite = Some(String::from(suite));
+		}
+
+		let request = one_line(&result.request).replace('#', "\\#");
+
+		// Subtests are written before the test point they belong to.
+		if !result.assertions.is_empty() {
+			let _ = writeln!(points, "    # Subtest: {}", request);
+
+			for (i, assertion) in result.assertions.iter().enumerate() {
+				let name = assertion.assertion.to_string().replace('#', "\\#");
+				write_point(points, 4, assertion.passed(), i + 1, &name);
+
+				if let Some(x) = &assertion.failure {
+					let _ = writeln!(points, "      ---");
+					write_message(points, 6, x);
+					let _ = writeln!(points, "      ...");
+				}
+			}
+
+			let _ = writeln!(points, "    1..{}", result.assertions.len());
+		}
+
+		write_point(points, 0, result.passed(), self.count, &request);
+
+		let _ = writeln!(points, "  ---");
+		let _ = writeln!(points, "  duration_ms: {}", result.duration.as_millis());
+
+		if let Some(x) = result.status {
+			let _ = writeln!(points, "  status: {}", x);
+		}
+
+		if let Some(x) = &result.error {
+			write_message(points, 2, &x.to_string());
+		}
+
+		let _ = writeln!(points, "  ...");
+	}
+
+	fn finish(&mut self) -> io::Result<()> {
+		writeln!(self.out, "TAP version 13")?;
+		writeln!(self.out, "1..{}", self.count)?;
+		write!(self.out, "{}", self.points)?;
+		self.out.flush()
+	}
+}
+
+/// Write a TAP test point, indented by `indent` spaces.
+fn write_point(points: &mut String, indent: usize, passed: bool, number: usize, name: &str) {
+	let _ = writeln!(
+		points,
+		"{:indent$}{} {} - {}",
+		"",
+		match passed {
+			true => "ok",
+			false => "not ok",
+		},
+		number,
+		name,
+	);
+}
+
+/// Write `message` as a YAML block scalar of a TAP test point,
+/// indented by `indent` spaces.
+fn write_message(points: &mut String, indent: usize, message: &str) {
+	let _ = writeln!(points, "{:indent$}message: |", "");
+
+	for line in message.lines() {
+		let _ = writeln!(points, "{:indent$}  {}", "", line);
+	}
+}
+
+/// Collapse all whitespace of a multi-line request into single spaces.
+pub fn one_line(request: &str) -> String {
+	request.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Escape `text` to be used in XML attributes and content.
+fn escape_xml(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+
+	for char in text.chars() {
+		match char {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			// Control chars other than whitespace are not allowed in XML.
+			x if x.is_control() && !x.is_whitespace() => (),
+			x => escaped.push(x),
+		}
+	}
+
+	escaped
+}
@@ -0,0 +1,102 @@
+use crate::GlueError;
+use gluescript::{Response, StatusPolicy};
+use reqwest::header::RETRY_AFTER;
+use std::{error::Error, io, time::Duration};
+
+/// Delay before the first retry, doubled at each subsequent one.
+const BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Maximum delay between two attempts, `Retry-After` excluded.
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Statuses retried when none are configured.
+const DEFAULT_STATUSES: &str = "429,502,503,504";
+
+/// When and how many times a failed request is sent again.
+///
+/// Requests are retried on connection errors, timeouts and on the
+/// configured response statuses, waiting an exponential backoff with
+/// jitter between attempts, or the time asked by a `Retry-After` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// Maximum number of retries, after the first attempt.
+	pub retries: u32,
+
+	/// Response statuses that are retried.
+	pub statuses: StatusPolicy,
+}
+
+impl RetryPolicy {
+	/// Create a new `RetryPolicy` retrying `retries` times
+	/// on the default statuses.
+	pub fn new(retries: u32) -> Self {
+		RetryPolicy {
+			retries,
+			statuses: StatusPolicy::parse(DEFAULT_STATUSES).unwrap(),
+		}
+	}
+
+	/// Whether a request failed with `error` should be sent again.
+	///
+	/// Only transient failures are retried: timeouts, failed connections
+	/// and connections lost while sending the request. Requests that can't
+	/// be built, or responses that can't be read, would fail again.
+	pub fn retries_error(&self, error: &GlueError) -> bool {
+		match error {
+			GlueError::Timeout(_) => true,
+			GlueError::Http(x) => {
+				x.is_connect() || x.is_timeout() || (x.is_request() && is_transport(x))
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether a request answered with `response` should be sent again.
+	pub fn retries_response(&self, response: &Response) -> bool {
+		self.statuses.contains(response.status)
+	}
+
+	/// Time to wait before sending again a request, after `attempt` failed
+	/// attempts. A `Retry-After` header in `response`, expressed in seconds,
+	/// takes precedence over the backoff.
+	pub fn delay(&self, attempt: u32, response: Option<&Response>) -> Duration {
+		let retry_after = response
+			.and_then(|x| x.headers.get(RETRY_AFTER))
+			.and_then(|x| x.to_str().ok())
+			.and_then(|x| x.trim().parse::<u64>().ok());
+
+		if let Some(seconds) = retry_after {
+			return Duration::from_secs(seconds);
+		}
+
+		// Exponential backoff, with a random jitter between
+		// half and the whole delay.
+		let backoff = BASE_DELAY
+			.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+			.min(MAX_DELAY);
+
+		backoff / 2 + backoff.mul_f64(fastrand::f64() / 2.0)
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		RetryPolicy::new(0)
+	}
+}
+
+/// Whether `error` was caused by an I/O failure of the connection,
+/// like a reset, rather than by the request or response themselves.
+fn is_transport(error: &(dyn Error + 'static)) -> bool {
+	let mut source = error.source();
+
+	while let Some(x) = source {
+		if x.is::<io::Error>() {
+			return true;
+		}
+
+		source = x.source();
+	}
+
+	false
+}
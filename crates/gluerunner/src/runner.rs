@@ -1,41 +1,77 @@
-use crate::MuxNode;
-
-use super::{http::execute_node, ExecutionStack, GlueNode, HeapMap};
-use std::{
-	fs,
-	sync::{Arc, Mutex},
+use crate::{
+	AssertionResult, ExecutionContext, GlueError, MuxNode, ResponseCache, RetryPolicy, Throttle,
 };
 
+use super::{graph::ExecutionGraph, http::execute_node, GlueNode, HeapMap};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use gluescript::{constants, OperatorKind, RequestDefaults, Response, StatusPolicy};
+use reqwest::Client;
+use std::{fs, panic, sync::Arc};
+use tokio::{sync::Mutex, task::JoinHandle};
+
 /// A component responsible for `GlueNode` execution.
-/// All `GlueNode` are sorted into different layers based on their depth and
-/// dependance between each other.
-///
-/// Layers are executed one-at-a-time, and every `GlueNode` of the same layer will run
-/// on parallel.
+/// All `GlueNode` are placed in a dependency graph, and each of them
+/// is executed as soon as the nodes it depends on are executed.
 #[derive(Debug)]
 pub struct Runner {
 	/// Root `GlueNode` of the runner. Holds the ownership of the main request
-	/// struct to be executed. `self.layers` are build starting from this.
+	/// struct to be executed. `self.graph` is built starting from this.
 	pub root: MuxNode,
 
-	/// Vector of Vector of `Runner`. Each `Runner` is behind an `Arc<Mutex<T>>`
-	/// to enable concurrent access to the same shared memory between runners.
-	/// Every `Runner` is a reference to a `MuxNode` part of `Runner.root`, so
-	/// reads and writes on this object are reflected on `Runner.root`.
-	pub layers: ExecutionStack,
+	/// Source text of the root request, as written by the user.
+	pub command: String,
+
+	/// Whether the root request assigns a variable with `let`, sending
+	/// nothing itself. Assignments are neither printed nor reported,
+	/// unless they fail.
+	pub assignment: bool,
 
-	/// Number representing `Runner` max depth, equal to the max depth between
-	/// all the `GlueNode` in it.
-	pub depth: usize,
+	/// Dependency graph of all the `GlueNode` in the runner. Every node is a
+	/// reference to a `MuxNode` part of `Runner.root`, so reads and writes on
+	/// them are reflected on `Runner.root`.
+	pub graph: ExecutionGraph,
+
+	/// Names of the heap variables read by the nodes of the runner.
+	pub reads: Vec<String>,
+
+	/// Names of the heap variables the nodes of the runner save their results in.
+	pub saves: Vec<String>,
 
 	/// Option containing the final result of the `Runner`, also equal to the result
 	/// of the `GlueNode` with depth 0
 	pub result: Option<String>,
 
+	/// Option containing the whole http response of the `GlueNode` with depth 0.
+	/// `None` if not executed yet, or if its result was read from memory.
+	pub response: Option<Response>,
+
+	/// Outcomes of the assertions checked in the last execution, in order.
+	/// Assertions of nodes that were never executed are not included.
+	pub assertions: Vec<AssertionResult>,
+
 	/// A shared memory map used to read and write `GlueNode` results their reuse in
 	/// subsequent runs.
 	pub heap: HeapMap,
 
+	/// Http client used by every `GlueNode`. Clones of a `Client` share the
+	/// same connection pool, so a single one should be shared between runners.
+	pub client: Client,
+
+	/// Response statuses accepted as a success by every `GlueNode`,
+	/// other than 2xx and 3xx.
+	pub accept: StatusPolicy,
+
+	/// Retry policy of every `GlueNode`. Only requests with
+	/// idempotent methods are retried, unless set per request.
+	pub retry: RetryPolicy,
+
+	/// Base url and headers of all requests.
+	pub defaults: RequestDefaults,
+
+	/// Limits on requests in flight and requests per host of every
+	/// `GlueNode`. Clones share the same limits.
+	pub throttle: Throttle,
+
 	/// Boolean to toggle verbose mode.
 	/// In verbose mode each executed `GlueNode` also prints request info
 	/// before is run.
@@ -43,151 +79,204 @@ pub struct Runner {
 }
 
 impl Runner {
-	/// A new `Runner` consists in an empty `HeapMap` and a single, empty layer.
-	/// For convenience, `depth` will default to 0 even if there's no `GlueNode`
-	/// with `depth` 0 in it.
-	fn new(root: MuxNode, heap: HeapMap, log_info: bool) -> Self {
-		// Initial `Runner` instance with `root` ownership and initial empty
-		// layers and heap.
-		let mut runner = Runner {
+	/// A new `Runner` consists in the dependency graph of `root`, executed
+	/// with `heap` and `client`.
+	fn new(root: GlueNode, heap: HeapMap, client: Client, log_info: bool) -> Self {
+		let command = root.command.clone();
+		let assignment = root.request.method == constants::LET;
+		let root = Arc::new(Mutex::new(root));
+
+		// The graph is built starting from `root`, creating multiple
+		// references to the same mutexes.
+		let graph = ExecutionGraph::from_root(Arc::clone(&root));
+		let (reads, saves) = heap_variables(&graph);
+
+		Runner {
+			graph,
 			root,
-			layers: vec![vec![]],
-			depth: 0,
+			command,
+			assignment,
+			reads,
+			saves,
 			result: None,
+			response: None,
+			assertions: vec![],
 			heap,
+			client,
+			accept: StatusPolicy::default(),
+			retry: RetryPolicy::default(),
+			defaults: RequestDefaults::default(),
+			throttle: Throttle::default(),
 			log_info,
-		};
-
-		// `runner` layers are built starting from `root`, creating multiple
-		// references behind mutexes and placing them in the layers matrix.
-		runner.build_layers();
-		runner
+		}
 	}
 
 	/// Create a Runner instance containing the provided `GlueNode` along with
 	/// all its dependency nodes.
-	pub fn from_root_node(root: GlueNode, heap: HeapMap, log_info: bool) -> Self {
-		// The runner is created using the provided heap map and wrapping
+	pub fn from_root_node(root: GlueNode, heap: HeapMap, client: Client, log_info: bool) -> Self {
+		// The runner is created using the provided heap map and client, wrapping
 		// `root` in a Mutex, as will be mutually accessed.
-		Runner::new(Arc::new(Mutex::new(root)), heap, log_info)
+		Runner::new(root, heap, client, log_info)
 	}
 
 	/// Create a `GlueNode` instance starting from provided `command` and then
 	/// create a Runner instance from the `GlueNode`, along with all its dependency nodes.
-	pub fn from_string(command: &String, heap: HeapMap, log_info: bool) -> Result<Self, String> {
-		match GlueNode::from_string(command) {
-			Err(x) => Err(x),
-			Ok(x) => Ok(Runner::from_root_node(x, heap, log_info)),
-		}
+	pub fn from_string(
+		command: &str,
+		heap: HeapMap,
+		client: Client,
+		log_info: bool,
+	) -> Result<Self, GlueError> {
+		let root = GlueNode::from_string(command)?;
+		Ok(Runner::from_root_node(root, heap, client, log_info))
 	}
 
 	/// Create a `GlueNode` instance starting from file content at `path` and then
 	/// create a Runner instance from the `GlueNode`, along with all its dependency nodes.
-	pub fn from_file(path: &String, heap: HeapMap, log_info: bool) -> Result<Self, String> {
+	pub fn from_file(
+		path: &String,
+		heap: HeapMap,
+		client: Client,
+		log_info: bool,
+	) -> Result<Self, GlueError> {
 		// Get file at `path` or propagate error.
-		let command = match fs::read_to_string(path) {
-			Err(x) => return Err(x.to_string()),
-			Ok(x) => x,
-		};
+		let command = fs::read_to_string(path)?;
 
 		// Create `GlueNode` from file content, propagate error on fail.
-		match GlueNode::from_string(&command) {
-			Err(x) => Err(x),
-			Ok(x) => Ok(Runner::from_root_node(x, heap, log_info)),
-		}
+		Runner::from_string(&command, heap, client, log_info)
 	}
 
-	/// Add a `GlueNode` to the `Runner` automatically determining the layer it belongs to.
-	pub fn add_node(self: &mut Self, node: MuxNode) -> () {
-		// Acquire readable lock on `node` mutex
-		let r_node = node.lock().unwrap();
+	/// Execute the `Runner` following its dependency graph.
+	///
+	/// Each `GlueNode` is spawned on its own task as soon as all of its own
+	/// dependencies are executed, without waiting for unrelated nodes, so
+	/// independent requests run concurrently. The first failure stops the
+	/// execution, aborting all running nodes, so nodes depending on a failed
+	/// one are never executed.
+	///
+	/// Outcomes of all checked assertions are kept in `self.assertions`,
+	/// and the root response in `self.response`, even when the execution fails.
+	pub async fn execute(&mut self) -> Result<(), GlueError> {
+		// Settings shared by all nodes, with responses of identical requests
+		// and assertions shared during this execution only.
+		let context = ExecutionContext {
+			heap: Arc::clone(&self.heap),
+			client: self.client.clone(),
+			accept: self.accept.clone(),
+			retry: self.retry.clone(),
+			defaults: self.defaults.clone(),
+			throttle: self.throttle.clone(),
+			responses: ResponseCache::default(),
+			assertions: Default::default(),
+			log_info: self.log_info,
+		};
+
+		let result = self.execute_graph(&context).await;
+		self.assertions = context.assertions.lock().unwrap().clone();
 
-		if self.depth < r_node.depth {
-			// If layer is not existing yet, create it.
-			self.add_layer();
-		}
+		// Root response is kept even when the execution fails, as it
+		// is received before its assertions are checked.
+		let root = self.root.lock().await;
+		self.response = root.response.clone();
+		result?;
 
-		// Release lock on node mutex
-		drop(r_node);
+		// Assign root node result to `Runner`.
+		self.result = Some(String::from(&root.result));
 
-		// Node is pushed wrapped in a `Arc<Mutex<T>>` to be shared across threads and
-		// accessed concurrently.
-		self.layers[self.depth].push(node);
+		Ok(())
 	}
 
-	/// Execute the `Runner` layer by layer.
-	/// Layers are executed one-at-a-time, while every `GlueNode`
-	/// of the same layer is executed concurrently.
-	///
-	/// A green tokio task is spawned for each `GlueNode` to be executed and all tasks
-	/// are awaited before continuing to the next layer
-	pub async fn execute(self: &mut Self) -> Result<(), String> {
-		// Each layer is executed granularly, and waiting for its termination
-		// before continuing to the next layer.
-		for layer in self.layers.iter() {
-			// An array of lazy tasks to be awaited.
-			let mut tasks = vec![];
-
-			for request in layer.iter() {
-				// We lock the node in write mode.
-				let mut w_request = request.lock().unwrap();
-
-				// As all dependencies should have been executed already, `request`
-				// can resolve all of its dependencies.
-				w_request.resolve_dependencies();
-
-				// Execution routine is pushed into execution pipe, ready to be polled
-				tasks.push(execute_node(
-					Arc::clone(&request),
-					Arc::clone(&self.heap),
-					self.log_info,
-				))
+	/// Execute all nodes of the graph with `context`, stopping on
+	/// the first failure.
+	async fn execute_graph(&self, context: &ExecutionContext) -> Result<(), GlueError> {
+		// Number of dependencies still to be executed for each node.
+		let mut pending: Vec<usize> = self.graph.dependencies.iter().map(|x| x.len()).collect();
+
+		// Tasks of the nodes currently executing, resolving to their index.
+		let mut running = FuturesUnordered::new();
+
+		for index in self.graph.leaves() {
+			running.push(self.start_node(index, context.clone()));
+		}
+
+		while let Some(task) = running.next().await {
+			// Panics of a node are propagated as they are.
+			let (index, result) = task.unwrap_or_else(|x| panic::resume_unwind(x.into_panic()));
+
+			if let Err(error) = result {
+				running.iter().for_each(JoinHandle::abort);
+				return Err(error);
 			}
 
-			for task in tasks {
-				// Polling on each task to block execution flow till they are ready.
-				// Await is done by the main thread.
-				task.await?;
+			// Dependents are started as soon as their last dependency is done.
+			for dependent in &self.graph.dependents[index] {
+				pending[*dependent] -= 1;
+
+				if pending[*dependent] == 0 {
+					running.push(self.start_node(*dependent, context.clone()));
+				}
 			}
 		}
 
-		// Assign root node result to `Runner` result.
-		self.result = Some(String::from(&self.root.lock().unwrap().result));
-
 		Ok(())
 	}
 
-	/// Create a new empty layer in the `Runner`, increasing also
-	/// its depth.
-	fn add_layer(self: &mut Self) -> () {
-		self.layers.push(vec![]);
-		self.depth += 1;
-	}
+	/// Spawn the execution of the node at `index` in the graph with `context`,
+	/// returning a task resolving to its index and its execution result.
+	///
+	/// All dependencies of the node must have been executed already.
+	fn start_node(
+		&self,
+		index: usize,
+		context: ExecutionContext,
+	) -> JoinHandle<(usize, Result<(), GlueError>)> {
+		let node = Arc::clone(&self.graph.nodes[index]);
+
+		tokio::spawn(async move {
+			// As all dependencies have been executed, the node can
+			// resolve all of them.
+			node.lock().await.resolve_dependencies().await;
 
-	/// Recursively add `GlueNode` to stack, along with all its dependencies.
-	fn add_node_recursive(self: &mut Self, node: MuxNode) -> () {
-		self.add_node(Arc::clone(&node));
+			(index, execute_node(node, context).await)
+		})
+	}
 
-		// Acquire readable lock on `node` to iterate dependencies
-		let r_node = node.lock().unwrap();
+	/// Render the result of the `Runner` for the user.
+	/// If `include` is true, the result is preceded by the response status
+	/// line and headers, as they were received.
+	pub fn output(&self, include: bool) -> String {
+		let result = self.result.clone().unwrap_or_default();
 
-		for dep_node in &r_node.dependencies {
-			// Recursively add a Mutex clone of the dependency
-			// to the layers matrix.
-			self.add_node_recursive(Arc::clone(dep_node));
+		match &self.response {
+			Some(response) if include => format!("{}\n\n{}", response.head(), result),
+			_ => result,
 		}
 	}
+}
+
+/// Names of the heap variables read and saved by all nodes of `graph`.
+fn heap_variables(graph: &ExecutionGraph) -> (Vec<String>, Vec<String>) {
+	let mut reads = vec![];
+	let mut saves = vec![];
+
+	for node in &graph.nodes {
+		// The graph is just built, so nodes are not locked by anyone.
+		let node = node
+			.try_lock()
+			.expect("nodes are not locked before execution");
+		let request = &node.request;
 
-	/// Populate `self.layers` with dependency referenced mutexes
-	/// starting from `self.root`
-	fn build_layers(self: &mut Self) -> () {
-		// Layers are built starting from a new Mutex clone
-		self.add_node_recursive(Arc::clone(&self.root));
-
-		// Layers array is reversed to be able to execute the `GlueNode` with
-		// highest depth first, and incrementally going to the 0 one.
-		// This is necessary as depth with lower depth will always be dependant
-		// on the ones with higher depth.
-		self.layers.reverse();
+		// Saved variables are read with `req`, using their name as url.
+		if request.method == constants::REQ {
+			reads.extend(request.url.resolve());
+		}
+
+		for operator in &request.operators {
+			if let OperatorKind::SaveAs(name) = &operator.kind {
+				saves.push(name.clone());
+			}
+		}
 	}
+
+	(reads, saves)
 }
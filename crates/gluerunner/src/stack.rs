@@ -1,6 +1,49 @@
-use crate::{heap, HeapMap, Runner};
-use gluescript::GlueNode;
-use std::{fs, sync::Arc};
+use crate::{
+	heap, AssertionResult, GlueError, HeapMap, RetryPolicy, Runner, SharedReporter, Throttle,
+};
+use gluescript::{
+	constants, parse, parse_request, GlueNode, Operator, OperatorKind, ParseError, RequestDefaults,
+	Span, StatusPolicy,
+};
+use reqwest::Client;
+use std::{
+	collections::HashSet,
+	fs,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+/// Suite name of runners not loaded from a file.
+const DEFAULT_SUITE: &str = "glue";
+
+/// What a `Stack` does with the following runners when a `Runner` fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+	/// Stop the execution on the first failure.
+	#[default]
+	Stop,
+
+	/// Execute all runners, reporting all failures at the end.
+	Continue,
+
+	/// Like `Continue`, but skip runners using variables saved by failed
+	/// runners, as they would fail too.
+	Skip,
+}
+
+impl ErrorPolicy {
+	/// Parse a policy from its name, `stop`, `continue` or `skip`.
+	///
+	/// `None` is returned if `name` is not a policy.
+	pub fn parse(name: &str) -> Option<Self> {
+		match name {
+			"stop" => Some(ErrorPolicy::Stop),
+			"continue" => Some(ErrorPolicy::Continue),
+			"skip" => Some(ErrorPolicy::Skip),
+			_ => None,
+		}
+	}
+}
 
 /// A sequential executor of `Runner` instances.
 ///
@@ -9,12 +52,25 @@ use std::{fs, sync::Arc};
 ///
 /// Can be created empty:
 /// ```rust
+/// # use gluerunner::Stack;
 /// let stack = Stack::new();
 /// ```
 ///
 /// Or starting from a `GlueNode`:
 /// ```rust
-/// let stack = Stack::from_root_node(node);
+/// # use gluerunner::Stack;
+/// # use gluescript::GlueNode;
+/// # let node = GlueNode::from_string("get https://example.com").unwrap();
+/// let stack = Stack::from_root_node(node, false);
+/// ```
+///
+/// Or sending all requests with a configured client:
+/// ```rust
+/// # use gluerunner::Stack;
+/// # use reqwest::Client;
+/// # use std::time::Duration;
+/// let client = Client::builder().timeout(Duration::from_secs(5)).build().unwrap();
+/// let stack = Stack::with_client(client);
 /// ```
 pub struct Stack {
 	/// The vector of `Runner` instances that will be executed
@@ -26,21 +82,133 @@ pub struct Stack {
 	/// from different tasks
 	heap: HeapMap,
 
+	/// Http client shared by every `Runner`, so that connections
+	/// are reused across all requests.
+	client: Client,
+
 	/// An index to the next `Runner` to be executed.
 	current: usize,
+
+	/// Whether results are printed along with the response status
+	/// line and headers.
+	include: bool,
+
+	/// Response statuses accepted as a success by every `Runner`,
+	/// other than 2xx and 3xx.
+	accept: StatusPolicy,
+
+	/// Retry policy of every `Runner`.
+	retry: RetryPolicy,
+
+	/// Base url and headers of the requests of every `Runner`.
+	defaults: RequestDefaults,
+
+	/// Limits shared by every `Runner`, so they apply to
+	/// all requests of the `Stack`.
+	throttle: Throttle,
+
+	/// Reporters recording every executed `Runner`.
+	reporters: Vec<SharedReporter>,
+
+	/// What to do with the following runners when a `Runner` fails.
+	error_policy: ErrorPolicy,
+
+	/// Variables that failed runners would have saved, and no
+	/// following `Runner` saved yet.
+	failed_variables: HashSet<String>,
+
+	/// Name of the suite the runners are reported in, the path
+	/// of the last pushed file if any.
+	suite: String,
 }
 
 impl Stack {
 	/// Create a new `Stack` instance with empty runners `Vec`.
 	/// an empty `Arc<Mutex<HashMap>>` is used as heap.
 	pub fn new() -> Self {
+		Stack::with_client(Client::new())
+	}
+
+	/// Create a new `Stack` instance with empty runners `Vec`, sending
+	/// all requests with `client`.
+	///
+	/// This allows to configure timeouts, proxies and TLS settings
+	/// once for all requests.
+	pub fn with_client(client: Client) -> Self {
 		Stack {
 			runners: vec![],
 			heap: heap(),
+			client,
 			current: 0,
+			include: false,
+			accept: StatusPolicy::default(),
+			retry: RetryPolicy::default(),
+			defaults: RequestDefaults::default(),
+			throttle: Throttle::default(),
+			reporters: vec![],
+			error_policy: ErrorPolicy::default(),
+			failed_variables: HashSet::new(),
+			suite: String::from(DEFAULT_SUITE),
 		}
 	}
 
+	/// Set the http client used by every `Runner` pushed from now on.
+	pub fn set_client(&mut self, client: Client) {
+		self.client = client;
+	}
+
+	/// Set response statuses accepted by every `Runner` pushed
+	/// from now on.
+	pub fn set_accept(&mut self, accept: StatusPolicy) {
+		self.accept = accept;
+	}
+
+	/// Set the retry policy of every `Runner` pushed from now on.
+	pub fn set_retry(&mut self, retry: RetryPolicy) {
+		self.retry = retry;
+	}
+
+	/// Set the base url and headers of the requests of every `Runner`
+	/// pushed from now on.
+	pub fn set_defaults(&mut self, defaults: RequestDefaults) {
+		self.defaults = defaults;
+	}
+
+	/// Save `value` in the heap as the variable `name`, so that
+	/// every `Runner` can read it.
+	pub fn set_variable(&mut self, name: &str, value: &str) {
+		self.heap
+			.lock()
+			.unwrap()
+			.insert(String::from(name), String::from(value));
+	}
+
+	/// Set the limits on requests of every `Runner` pushed from now on.
+	pub fn set_throttle(&mut self, throttle: Throttle) {
+		self.throttle = throttle;
+	}
+
+	/// Record every `Runner` executed from now on in `reporter`.
+	pub fn add_reporter(&mut self, reporter: SharedReporter) {
+		self.reporters.push(reporter);
+	}
+
+	/// Set what to do with the following runners when a `Runner` fails.
+	pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+		self.error_policy = policy;
+	}
+
+	/// Set the name of the suite the runners are reported in.
+	pub fn set_suite(&mut self, suite: &str) {
+		self.suite = String::from(suite);
+	}
+
+	/// Toggle printing of the response status line and headers
+	/// before each result.
+	pub fn set_include(&mut self, include: bool) {
+		self.include = include;
+	}
+
 	/// Create a new `Stack` instance starting from a `GlueNode`.
 	/// The created `GlueNode` will receive the `Arc` heap map from the fresh `Stack`.
 	pub fn from_root_node(root: GlueNode, log_info: bool) -> Self {
@@ -48,7 +216,12 @@ impl Stack {
 
 		// The same Arc is cloned in every `Runner` of the stack, so all every
 		// `GlueNode` will concurrently access to the same memory.
-		let runner = Runner::from_root_node(root, Arc::clone(&stack.heap), log_info);
+		let runner = Runner::from_root_node(
+			root,
+			Arc::clone(&stack.heap),
+			stack.client.clone(),
+			log_info,
+		);
 
 		stack.push_runner(runner);
 		stack
@@ -56,17 +229,69 @@ impl Stack {
 
 	/// Read content from file and push everything in the stack from it.
 	/// Allow to create a stack from a file containing more than one root `GlueNode`
-	pub fn push_from_file(self: &mut Self, path: String, log_info: bool) -> Result<(), String> {
+	pub fn push_from_file(&mut self, path: String, log_info: bool) -> Result<(), GlueError> {
 		// Read content from file and propagate error on failure
-		let content = match fs::read_to_string(path) {
-			Err(x) => return Err(x.to_string()),
-			Ok(x) => x,
-		};
+		let content = fs::read_to_string(&path)?;
+
+		// Runners of the file are reported in a suite named after it.
+		self.suite = path;
+
+		self.push_from_script(content, log_info)
+	}
+
+	/// Push everything in the stack from `script`, containing any number
+	/// of root `GlueNode` separated by `;`, like a file.
+	pub fn push_from_script(&mut self, script: String, log_info: bool) -> Result<(), GlueError> {
+		// All requests share the script as source, so errors
+		// can be located in it.
+		let source: Arc<str> = Arc::from(script);
+
+		// Each command in the script must be separated by `;`
+		let script = parse(&source)?;
+
+		// The base url of the script is used by its runners only,
+		// and only if the stack has none.
+		let mut defaults = self.defaults.clone();
+		defaults.base_url = defaults
+			.base_url
+			.or_else(|| script.base_url().map(String::from));
+
+		// Headers of the script are added to all of its requests, unless the
+		// stack has its own header with the same name.
+		let headers: Vec<Operator> = script
+			.headers()
+			.into_iter()
+			.filter(|x| match &x.kind {
+				OperatorKind::Header { key, .. } => !defaults
+					.headers
+					.keys()
+					.any(|x| x.as_str().eq_ignore_ascii_case(key)),
+				_ => true,
+			})
+			.collect();
+
+		// Runners are only pushed once all variables they read are known,
+		// so a script is either pushed as a whole or not at all.
+		let mut runners = vec![];
+
+		for mut request in script.requests {
+			request.add_headers(&headers);
+			let reads = request.variable_reads();
 
-		// Each command in the file must end with `;`
-		for command in content.split(';') {
-			// Add command directly to stack, without executing it.
-			self.push_runner_from_string(&command.to_owned(), log_info)?;
+			let root = GlueNode::new(Arc::clone(&source), request, 0);
+			runners.push(Runner::from_root_node(
+				root,
+				Arc::clone(&self.heap),
+				self.client.clone(),
+				log_info,
+			));
+
+			self.check_variables(&reads, &runners, &source)?;
+		}
+
+		// Add commands directly to stack, without executing them.
+		for runner in runners {
+			self.push_runner_with_defaults(runner, defaults.clone());
 		}
 
 		Ok(())
@@ -78,30 +303,85 @@ impl Stack {
 	/// If the `Runner` creation fails for some reason, an `Err` is returned.
 	/// The created `Runner` will receive the `Arc` heap map from the fresh `Stack`.
 	pub fn push_runner_from_string(
-		self: &mut Self,
-		command: &String,
+		&mut self,
+		command: &str,
 		log_info: bool,
-	) -> Result<(), String> {
+	) -> Result<(), GlueError> {
+		let request = parse_request(command)?;
+		let reads = request.variable_reads();
+
 		// The `Stack` Arc heap is cloned in the `Runner`, so every `GlueNode`
 		// contained in it will concurrently access to the same memory.
-		let runner = Runner::from_string(command, Arc::clone(&self.heap), log_info)?;
+		let runner = Runner::from_root_node(
+			GlueNode::new(Arc::from(command), request, 0),
+			Arc::clone(&self.heap),
+			self.client.clone(),
+			log_info,
+		);
 
-		self.runners.push(runner);
+		self.check_variables(&reads, std::slice::from_ref(&runner), command)?;
+		self.push_runner(runner);
 		Ok(())
 	}
 
+	/// Check that every variable read as `$name` in `source`, listed in
+	/// `reads`, is in the heap or saved by a runner of the stack or of
+	/// `pending`, so misspelled names and unescaped `$` are reported
+	/// before any request is sent.
+	///
+	/// Variables read with `{req name}` are only checked on execution.
+	fn check_variables(
+		&self,
+		reads: &[(String, Span)],
+		pending: &[Runner],
+		source: &str,
+	) -> Result<(), ParseError> {
+		let heap = self.heap.lock().unwrap();
+		let is_known = |name: &str| {
+			heap.contains_key(name)
+				|| self
+					.runners
+					.iter()
+					.chain(pending)
+					.any(|x| x.saves.iter().any(|x| x == name))
+		};
+
+		let unknown = reads.iter().find(|(name, span)| {
+			source[span.start..].starts_with(constants::VARIABLE_START) && !is_known(name)
+		});
+
+		match unknown {
+			None => Ok(()),
+			Some((_, span)) => Err(
+				ParseError::new(constants::ERR_UNKNOWN_VARIABLE, *span, source)
+					.with_help(constants::HELP_UNKNOWN_VARIABLE),
+			),
+		}
+	}
+
 	/// Add a `Runner` to the `Stack`
-	pub fn push_runner(self: &mut Self, mut runner: Runner) -> () {
+	pub fn push_runner(&mut self, runner: Runner) {
+		self.push_runner_with_defaults(runner, self.defaults.clone());
+	}
+
+	/// Add a `Runner` to the `Stack`, sending its requests with `defaults`
+	/// in place of the ones of the `Stack`.
+	fn push_runner_with_defaults(&mut self, mut runner: Runner, defaults: RequestDefaults) {
 		// Heap is cloned from the `Stack` so the `Runner` can access
 		// the same memory to read and write response variables
 		runner.heap = Arc::clone(&self.heap);
+		runner.client = self.client.clone();
+		runner.accept = self.accept.clone();
+		runner.retry = self.retry.clone();
+		runner.defaults = defaults;
+		runner.throttle = self.throttle.clone();
 
 		// Runner is simply pushed into the array as owned
 		self.runners.push(runner);
 	}
 
 	/// Execute the next `Runner` in the `Stack` and increases the
-	pub async fn execute_next(self: &mut Self) -> Result<(), String> {
+	pub async fn execute_next(&mut self) -> Result<(), GlueError> {
 		// The next runner to be executed is takes by `self.next` index
 		let runner = &mut self.runners[self.current];
 
@@ -116,24 +396,117 @@ impl Stack {
 		Ok(())
 	}
 
-	pub async fn execute_all(self: &mut Self) -> Result<(), String> {
-		loop {
-			if self.current > self.runners.len() - 1 {
-				break;
+	/// Execute all the remaining `Runner` in the `Stack`, printing the
+	/// result of each one but variable assignments.
+	///
+	/// The execution stops on the first failure, unless the error policy
+	/// allows to continue. In that case all failures are returned at the end
+	/// as `GlueError::Failures`.
+	pub async fn execute_all(&mut self) -> Result<(), GlueError> {
+		let mut failures = vec![];
+
+		while self.current < self.runners.len() {
+			match self.execute_reported().await.error {
+				None if self.current().unwrap().assignment => (),
+				None => println!("{}", self.current().unwrap().output(self.include)),
+				Some(x) if self.error_policy == ErrorPolicy::Stop => return Err(x),
+				Some(x) => failures.push(x),
 			}
+		}
+
+		match failures.is_empty() {
+			true => Ok(()),
+			false => Err(GlueError::Failures(failures)),
+		}
+	}
+
+	/// Execute all the remaining `Runner` in the `Stack` as tests, returning
+	/// the outcome of each one. Failures don't stop the execution of the
+	/// following runners, but they are skipped if the error policy says so.
+	///
+	/// Variable assignments are only returned if they fail.
+	pub async fn execute_tests(&mut self) -> Vec<TestResult> {
+		let mut results = vec![];
 
-			self.execute_next().await?;
+		while self.current < self.runners.len() {
+			let result = self.execute_reported().await;
 
-			println!("{}", self.current().unwrap().result.clone().unwrap());
+			if result.error.is_some() || !self.current().unwrap().assignment {
+				results.push(result);
+			}
 		}
 
-		Ok(())
+		results
+	}
+
+	/// Execute the next `Runner` in the `Stack`, recording its outcome
+	/// in all reporters. Variable assignments are only recorded if they fail.
+	async fn execute_reported(&mut self) -> TestResult {
+		let start = Instant::now();
+
+		let error = match self.skipped_by() {
+			None => self.execute_next().await.err(),
+			Some(variable) => {
+				self.current += 1;
+				Some(GlueError::Skipped {
+					command: self.current().unwrap().command.clone(),
+					variable,
+				})
+			}
+		};
+
+		// Variables are failed until a following `Runner` saves them.
+		let runner = &self.runners[self.current - 1];
+		for x in &runner.saves {
+			match error {
+				None => self.failed_variables.remove(x),
+				Some(_) => self.failed_variables.insert(x.clone()),
+			};
+		}
+
+		// Requests failed for their status have no response, but
+		// the status is still reported.
+		let status = match (&runner.response, &error) {
+			(Some(x), _) => Some(x.status.as_u16()),
+			(None, Some(GlueError::Status { status, .. })) => Some(status.as_u16()),
+			_ => None,
+		};
+
+		let result = TestResult {
+			request: runner.command.clone(),
+			assertions: runner.assertions.clone(),
+			duration: start.elapsed(),
+			status,
+			error,
+		};
+
+		if result.error.is_some() || !runner.assignment {
+			for reporter in &self.reporters {
+				reporter.lock().unwrap().record(&self.suite, &result);
+			}
+		}
+
+		result
+	}
+
+	/// The first variable used by the next `Runner` that a failed `Runner`
+	/// would have saved, if runners using them are skipped.
+	fn skipped_by(&self) -> Option<String> {
+		if self.error_policy != ErrorPolicy::Skip {
+			return None;
+		}
+
+		self.runners[self.current]
+			.reads
+			.iter()
+			.find(|x| self.failed_variables.contains(*x))
+			.cloned()
 	}
 
 	/// Get the next `Runner` to be executed wrapped in an `Option`.
 	///
 	/// Returns None if there is no `Runner` to execute.
-	pub fn current(self: &Self) -> Option<&Runner> {
+	pub fn current(&self) -> Option<&Runner> {
 		match self.current {
 			x if x > 0 => Some(&self.runners[x - 1]),
 			_ => None,
@@ -141,7 +514,40 @@ impl Stack {
 	}
 
 	/// Get the `HeapMap` from the `Stack`
-	pub fn heap(self: &Self) -> &HeapMap {
+	pub fn heap(&self) -> &HeapMap {
 		&self.heap
 	}
 }
+
+/// Outcome of a `Runner` executed as a test.
+#[derive(Debug)]
+pub struct TestResult {
+	/// Root request of the `Runner`, as written by the user.
+	pub request: String,
+
+	/// Outcomes of all the checked assertions, in order.
+	pub assertions: Vec<AssertionResult>,
+
+	/// Time taken by the whole `Runner`, nested requests included.
+	pub duration: Duration,
+
+	/// Response status of the root request, if one was received.
+	pub status: Option<u16>,
+
+	/// Why the `Runner` failed, if it did. Failed assertions are
+	/// reported as `GlueError::Assertion`.
+	pub error: Option<GlueError>,
+}
+
+impl TestResult {
+	/// Whether the `Runner` completed with all assertions passing.
+	pub fn passed(&self) -> bool {
+		self.error.is_none()
+	}
+}
+
+impl Default for Stack {
+	fn default() -> Self {
+		Stack::new()
+	}
+}
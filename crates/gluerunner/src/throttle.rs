@@ -0,0 +1,111 @@
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::{
+	sync::{OwnedSemaphorePermit, Semaphore},
+	time::{sleep_until, Instant},
+};
+
+/// Host name matching every host without a rate limit of its own.
+pub const ANY_HOST: &str = "*";
+
+/// Lowest rate limit, in requests per second: one request per day.
+pub const MIN_RATE: f64 = 1.0 / 86_400.0;
+
+/// Highest rate limit, in requests per second.
+pub const MAX_RATE: f64 = 1_000_000.0;
+
+/// Limits on how many requests are sent, and how fast.
+///
+/// A `Throttle` caps the number of requests in flight at once, and spaces
+/// requests to the same host to stay within their requests per second.
+/// Clones share the same limits, so a single `Throttle` is shared by all
+/// runners of a `Stack`.
+///
+/// ```rust
+/// # use gluerunner::Throttle;
+/// let mut throttle = Throttle::default();
+/// throttle.set_concurrency(10);
+/// throttle.set_rate("api.example.com", 5.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+	/// Permits of the requests in flight, `None` if unlimited.
+	concurrency: Option<Arc<Semaphore>>,
+
+	/// Requests per second allowed for each host.
+	rates: HashMap<String, f64>,
+
+	/// Time from which the next request to each host can be sent.
+	slots: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl Throttle {
+	/// Allow at most `max` requests in flight at once.
+	/// `max` must be greater than 0.
+	pub fn set_concurrency(&mut self, max: usize) {
+		self.concurrency = Some(Arc::new(Semaphore::new(max)));
+	}
+
+	/// Allow at most `rate` requests per second to `host`, or to each
+	/// host without a rate of its own if `host` is `ANY_HOST`.
+	/// `rate` is kept between `MIN_RATE` and `MAX_RATE`.
+	pub fn set_rate(&mut self, host: &str, rate: f64) {
+		self.rates
+			.insert(host.to_lowercase(), rate.clamp(MIN_RATE, MAX_RATE));
+	}
+
+	/// Parse a rate limit written as `host=rate`, or just `rate`
+	/// for every host, like `api.example.com=5`.
+	///
+	/// `None` is returned if `value` is not a valid rate limit, or the
+	/// rate is not between `MIN_RATE` and `MAX_RATE`.
+	pub fn parse_rate(value: &str) -> Option<(String, f64)> {
+		let (host, rate) = match value.split_once('=') {
+			None => (ANY_HOST, value),
+			Some((host, rate)) => (host.trim(), rate),
+		};
+
+		match rate.trim().parse::<f64>() {
+			Ok(x) if (MIN_RATE..=MAX_RATE).contains(&x) && !host.is_empty() => {
+				Some((String::from(host), x))
+			}
+			_ => None,
+		}
+	}
+
+	/// Wait until a request to `host` can be sent.
+	///
+	/// The returned permit counts as a request in flight until dropped.
+	pub async fn acquire(&self, host: Option<&str>) -> Option<OwnedSemaphorePermit> {
+		// A permit is taken before waiting for the rate limit, so requests
+		// are sent as soon as their time comes.
+		let permit = match &self.concurrency {
+			None => None,
+			Some(x) => Arc::clone(x).acquire_owned().await.ok(),
+		};
+
+		if let Some(slot) = host.and_then(|x| self.reserve_slot(x)) {
+			sleep_until(slot).await;
+		}
+
+		permit
+	}
+
+	/// Reserve the next time a request to `host` can be sent, if
+	/// a rate limit applies to it.
+	fn reserve_slot(&self, host: &str) -> Option<Instant> {
+		let host = host.to_lowercase();
+		let rate = self.rates.get(&host).or_else(|| self.rates.get(ANY_HOST))?;
+
+		let now = Instant::now();
+		let mut slots = self.slots.lock().unwrap();
+
+		let slot = slots.get(&host).map_or(now, |x| now.max(*x));
+		slots.insert(host, slot + Duration::from_secs_f64(1.0 / rate));
+
+		Some(slot)
+	}
+}
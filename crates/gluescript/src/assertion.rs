@@ -0,0 +1,31 @@
+use crate::Comparison;
+use regex::Regex;
+use std::fmt;
+
+/// An assertion on a response, resolved from a `%` operator.
+#[derive(Debug, Clone)]
+pub struct Assertion {
+	/// Response field, like `status` or `headers.etag`, or JSONPath
+	/// selector the assertion is about.
+	pub subject: String,
+
+	/// How the subject is compared with `expected`.
+	pub comparison: Comparison,
+
+	/// Expected value, empty if the subject only has to exist.
+	pub expected: String,
+
+	/// Compiled `expected` regular expression, if the subject has to match it.
+	pub pattern: Option<Regex>,
+}
+
+impl fmt::Display for Assertion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.comparison {
+			Comparison::Exists => write!(f, "{} exists", self.subject),
+			Comparison::Equals => write!(f, "{} = {}", self.subject, self.expected),
+			Comparison::NotEquals => write!(f, "{} != {}", self.subject, self.expected),
+			Comparison::Matches => write!(f, "{} ~= {}", self.subject, self.expected),
+		}
+	}
+}
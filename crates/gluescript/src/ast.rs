@@ -0,0 +1,325 @@
+use crate::{constants, Span};
+
+/// A whole gluescript source: requests, along with the directives
+/// applied to all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+	pub requests: Vec<Request>,
+	pub directives: Vec<Directive>,
+}
+
+/// A statement of a `Script` applied to all of its requests.
+///
+/// Variable assignments, like `let id = {get api.com/me^$.id}`, are not
+/// directives: they are requests saving their value, executed in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+	pub kind: DirectiveKind,
+	pub span: Span,
+}
+
+/// All the directives supported by gluescript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveKind {
+	/// `@base URL`, url that relative urls are resolved against.
+	Base(String),
+
+	/// `@header key=value`, header sent with every request.
+	Header { key: String, value: Value },
+}
+
+/// A single gluescript request, as written in the source:
+///
+/// ```text
+/// [METHOD] [URL] [OPERATORS]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+	/// Request method keyword, as written.
+	pub method: String,
+
+	/// Request url. It may contain nested requests.
+	pub url: Value,
+
+	/// Operators following the url, in source order.
+	pub operators: Vec<Operator>,
+
+	/// Position of the whole request in the source, from the
+	/// first char of the method to the last char of the last operator.
+	pub span: Span,
+}
+
+/// An operator applied to a `Request`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operator {
+	pub kind: OperatorKind,
+	pub span: Span,
+}
+
+/// All the operators supported by gluescript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperatorKind {
+	/// `^selector`: JSONPath selector applied to the response.
+	Selector(Value),
+
+	/// `~key=value`: JSON body attribute.
+	Body { key: String, value: Value },
+
+	/// `~#-json-#`: raw JSON body.
+	RawBody(String),
+
+	/// `*key=value`: request header.
+	Header { key: String, value: Value },
+
+	/// `*-key`: remove a default header from the request.
+	RemoveHeader(String),
+
+	/// `?key=value`: url query parameter.
+	Query { key: String, value: Value },
+
+	/// `>name`: save the response in a variable.
+	SaveAs(String),
+
+	/// `!statuses`: response statuses accepted as a success.
+	Accept(Value),
+
+	/// `@key=value`: request setting, like its timeout.
+	Setting { key: String, value: Value },
+
+	/// `%subject`, `%subject=value`, `%subject!=value` or `%subject~=pattern`:
+	/// assertion on a response field or JSONPath selection. `expected` is
+	/// `None` when the subject only has to exist.
+	Assert {
+		subject: String,
+		comparison: Comparison,
+		expected: Option<Value>,
+	},
+}
+
+/// How the subject of an assertion is compared with its expected value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+	/// The subject is found in the response.
+	Exists,
+
+	/// The subject is equal to the expected value.
+	Equals,
+
+	/// The subject is not equal to the expected value.
+	NotEquals,
+
+	/// The subject matches the expected regular expression.
+	Matches,
+}
+
+/// A value made of literal text and nested requests, concatenated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+	pub parts: Vec<ValuePart>,
+	pub span: Span,
+}
+
+/// A piece of `Value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValuePart {
+	/// Literal text.
+	Text(String),
+
+	/// A nested request. `span` also includes the delimiters.
+	/// If `each` is set, the nested request selects a list, and the owner
+	/// request is run once for each of its elements.
+	Nested {
+		request: Box<Request>,
+		span: Span,
+		each: bool,
+	},
+
+	/// Placeholder for the result of the nested request that has been
+	/// moved at this index of the owner `GlueNode` dependencies.
+	Dependency(usize),
+}
+
+impl Script {
+	/// Get the base url of the script, set by its last `@base` directive.
+	pub fn base_url(&self) -> Option<&str> {
+		self.directives.iter().rev().find_map(|x| match &x.kind {
+			DirectiveKind::Base(url) => Some(url.as_str()),
+			_ => None,
+		})
+	}
+
+	/// Get the headers of the script, set by its `@header` directives,
+	/// as header operators.
+	pub fn headers(&self) -> Vec<Operator> {
+		self.directives
+			.iter()
+			.filter_map(|x| match &x.kind {
+				DirectiveKind::Header { key, value } => Some(Operator {
+					kind: OperatorKind::Header {
+						key: key.clone(),
+						value: value.clone(),
+					},
+					span: x.span,
+				}),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+impl Request {
+	/// Add the header operators of `headers` to the request and all of its
+	/// nested requests, unless they set or remove a header with the same name.
+	///
+	/// Nested requests of the added headers are left untouched, as well as
+	/// requests reading or assigning variables, which send nothing.
+	pub fn add_headers(&mut self, headers: &[Operator]) {
+		for value in self.values_mut() {
+			for part in &mut value.parts {
+				if let ValuePart::Nested { request, .. } = part {
+					request.add_headers(headers);
+				}
+			}
+		}
+
+		if self.method == constants::REQ || self.method == constants::LET {
+			return;
+		}
+
+		for header in headers {
+			if let OperatorKind::Header { key, .. } = &header.kind {
+				if !self.has_header(key) {
+					self.operators.push(header.clone());
+				}
+			}
+		}
+	}
+
+	/// Get the names of the variables read by the nested requests of the
+	/// request, at any depth, along with the position of each of them.
+	pub fn variable_reads(&self) -> Vec<(String, Span)> {
+		let mut reads = vec![];
+
+		for value in self.values() {
+			for part in &value.parts {
+				if let ValuePart::Nested { request, span, .. } = part {
+					if let (constants::REQ, Some(name)) =
+						(request.method.as_str(), request.url.resolve())
+					{
+						reads.push((name, *span));
+					}
+
+					reads.extend(request.variable_reads());
+				}
+			}
+		}
+
+		reads
+	}
+
+	/// Check if the request sets or removes the header called `name`.
+	/// Header names are case insensitive.
+	pub fn has_header(&self, name: &str) -> bool {
+		self.operators.iter().any(|x| match &x.kind {
+			OperatorKind::Header { key, .. } | OperatorKind::RemoveHeader(key) => {
+				key.eq_ignore_ascii_case(name)
+			}
+			_ => false,
+		})
+	}
+
+	/// Get all values of the request, in source order.
+	pub fn values(&self) -> Vec<&Value> {
+		let mut values = vec![&self.url];
+
+		for operator in &self.operators {
+			match &operator.kind {
+				OperatorKind::Selector(value)
+				| OperatorKind::Accept(value)
+				| OperatorKind::Body { value, .. }
+				| OperatorKind::Header { value, .. }
+				| OperatorKind::Query { value, .. }
+				| OperatorKind::Setting { value, .. }
+				| OperatorKind::Assert {
+					expected: Some(value),
+					..
+				} => values.push(value),
+				_ => (),
+			}
+		}
+
+		values
+	}
+
+	/// Get all values of the request as mutable, in source order.
+	pub fn values_mut(&mut self) -> Vec<&mut Value> {
+		let mut values = vec![&mut self.url];
+
+		for operator in &mut self.operators {
+			match &mut operator.kind {
+				OperatorKind::Selector(value)
+				| OperatorKind::Accept(value)
+				| OperatorKind::Body { value, .. }
+				| OperatorKind::Header { value, .. }
+				| OperatorKind::Query { value, .. }
+				| OperatorKind::Setting { value, .. }
+				| OperatorKind::Assert {
+					expected: Some(value),
+					..
+				} => values.push(value),
+				_ => (),
+			}
+		}
+
+		values
+	}
+}
+
+impl Value {
+	/// Create an empty `Value` starting at `start`.
+	pub fn empty(start: usize) -> Self {
+		Value {
+			parts: vec![],
+			span: Span::new(start, start),
+		}
+	}
+
+	/// Whether no part has been added to the value.
+	pub fn is_empty(&self) -> bool {
+		self.parts.is_empty()
+	}
+
+	/// Append literal text, merging it with the last part if it is also text.
+	pub fn push_text(&mut self, text: &str) {
+		if let Some(ValuePart::Text(last)) = self.parts.last_mut() {
+			last.push_str(text);
+			return;
+		}
+
+		self.parts.push(ValuePart::Text(String::from(text)));
+	}
+
+	/// Replace the placeholder of dependency `index` with its `result`.
+	pub fn resolve_dependency(&mut self, index: usize, result: &str) {
+		for part in self.parts.iter_mut() {
+			if *part == ValuePart::Dependency(index) {
+				*part = ValuePart::Text(String::from(result));
+			}
+		}
+	}
+
+	/// Concatenate all parts into a string.
+	/// `None` is returned if a nested request has not been resolved yet.
+	pub fn resolve(&self) -> Option<String> {
+		let mut resolved = String::new();
+
+		for part in &self.parts {
+			match part {
+				ValuePart::Text(text) => resolved.push_str(text),
+				_ => return None,
+			}
+		}
+
+		Some(resolved)
+	}
+}
@@ -1,18 +1,148 @@
 pub const OPEN_DELIMITER: char = '{';
 pub const CLOSE_DELIMITER: char = '}';
+pub const QUOTE: char = '"';
+pub const ESCAPE: char = '\\';
+pub const SEPARATOR: char = ';';
+pub const COMMENT: char = '#';
+pub const EQUALS: char = '=';
+pub const LIST_SEPARATOR: char = ',';
+
+pub const SELECTOR_OPERATOR: char = '^';
+pub const BODY_OPERATOR: char = '~';
+pub const HEADER_OPERATOR: char = '*';
+pub const SAVE_AS_OPERATOR: char = '>';
+pub const QUERY_OPERATOR: char = '?';
+pub const ACCEPT_OPERATOR: char = '!';
+pub const SETTING_OPERATOR: char = '@';
+pub const ASSERT_OPERATOR: char = '%';
+pub const NOT: char = '!';
+pub const MATCHES: char = '~';
+
+pub const RAW_BODY_START: &str = "~#-";
+pub const RAW_BODY_END: &str = "-#";
+pub const URL_PATH_START: char = '/';
+pub const BASE: &str = "base";
+pub const HEADER: &str = "header";
+pub const REMOVE_HEADER: char = '-';
+pub const ENV_START: &str = "${";
+pub const ENV_END: char = '}';
+pub const ENV_DEFAULT: &str = ":-";
+pub const VARIABLE_START: char = '$';
 
 pub const GET: &str = "get";
 pub const POST: &str = "post";
 pub const PUT: &str = "put";
 pub const PATCH: &str = "patch";
 pub const DELETE: &str = "delete";
+pub const HEAD: &str = "head";
+pub const OPTIONS: &str = "options";
+pub const TRACE: &str = "trace";
 pub const REQ: &str = "req";
+pub const LET: &str = "let";
+pub const EACH: &str = "each";
+
+pub const FIELD_STATUS: &str = "status";
+pub const FIELD_URL: &str = "url";
+pub const FIELD_DURATION: &str = "duration";
+pub const FIELD_HEADERS: &str = "headers";
+pub const JSONPATH_ROOT: char = '$';
+
+pub const SETTING_TIMEOUT: &str = "timeout";
+pub const SETTING_RETRIES: &str = "retries";
+pub const SETTING_RETRY_ON: &str = "retry_on";
+pub const SETTING_DEDUPE: &str = "dedupe";
+
+pub const STATUS_CLASS: &str = "xx";
+pub const BODY_EXCERPT_LENGTH: usize = 200;
 
 pub const ERR_UNRESOLVED_METHOD: &str = "Failed to resolve method";
-pub const ERR_UNRESOLVED_URL: &str = "Failed to resolve method";
+pub const ERR_UNRESOLVED_URL: &str = "Failed to resolve url";
 pub const ERR_UNRESOLVED_ATTR_KEY: &str = "Failed to resolve key from attribute";
 pub const ERR_UNRESOLVED_ATTR_VAL: &str = "Failed to resolve value from attribute";
 pub const ERR_UNRESOLVED_VAL: &str = "Failed to resolve value";
 pub const ERR_UNRESOLVED_VAR: &str = "Failed to resolve var";
+pub const ERR_UNRESOLVED_DEPENDENCY: &str = "Failed to resolve nested request";
+
+pub const ERR_UNTERMINATED_STRING: &str = "Unterminated quoted value";
+pub const ERR_UNTERMINATED_RAW_BODY: &str = "Unterminated raw JSON body";
+pub const ERR_UNCLOSED_DELIMITER: &str = "Unclosed nested request";
+pub const ERR_UNCLOSED_ENV: &str = "Unclosed environment variable";
+pub const ERR_INVALID_ENV_NAME: &str = "Invalid environment variable name";
+pub const ERR_UNDEFINED_ENV: &str = "Undefined environment variable";
+pub const ERR_UNEXPECTED_CLOSE_DELIMITER: &str = "Unexpected closing delimiter";
+pub const ERR_UNEXPECTED_TOKEN: &str = "Unexpected token";
+pub const ERR_MULTIPLE_REQUESTS: &str = "Expected a single request";
+pub const ERR_MULTIPLE_EACH: &str = "Only one `each` nested request is allowed per request";
+pub const ERR_EACH_NOT_LIST: &str = "Nested request with `each` must select a JSON array";
+pub const ERR_INVALID_HEADER_NAME: &str = "Invalid header name";
+pub const ERR_INVALID_HEADER_VALUE: &str = "Invalid header value";
+pub const ERR_INVALID_RAW_BODY: &str = "Invalid raw JSON body";
+pub const ERR_UNKNOWN_RESPONSE_FIELD: &str = "Response has no field";
+pub const ERR_INVALID_STATUS: &str = "Invalid status";
+pub const ERR_UNKNOWN_SETTING: &str = "Unknown request setting";
+pub const ERR_INVALID_DURATION: &str = "Invalid duration";
+pub const ERR_INVALID_RETRIES: &str = "Invalid number of retries";
+pub const ERR_INVALID_REGEX: &str = "Invalid regular expression";
+pub const ERR_INVALID_BOOLEAN: &str = "Invalid boolean";
+pub const ERR_INVALID_CONCURRENCY: &str = "Invalid concurrency";
+pub const ERR_INVALID_RATE_LIMIT: &str = "Invalid rate limit";
+pub const ERR_REPORT_FILE: &str = "Unable to write report";
+pub const ERR_READ_STDIN: &str = "Unable to read script from stdin";
+pub const ERR_UNKNOWN_DIRECTIVE: &str = "Unknown directive";
+pub const ERR_UNEXPECTED_DIRECTIVE: &str = "Directives can only be used in scripts";
+pub const ERR_INVALID_BASE_URL: &str = "Invalid base url";
+pub const ERR_RELATIVE_URL: &str = "Relative url without a base url";
+pub const ERR_INVALID_LET: &str = "Invalid variable assignment";
+pub const ERR_INVALID_VARIABLE_NAME: &str = "Invalid variable name";
+pub const ERR_UNKNOWN_VARIABLE: &str = "Unknown variable";
+pub const ERR_READ_CONFIG: &str = "Unable to read config file";
+pub const ERR_INVALID_CONFIG: &str = "Invalid config file";
+pub const ERR_UNKNOWN_PROFILE: &str = "Unknown profile";
+pub const ERR_INVALID_HEADER: &str = "Invalid header, expected `name: value`";
+pub const ERR_INVALID_PROXY: &str = "Invalid proxy";
+pub const ERR_INVALID_ERROR_POLICY: &str =
+	"Invalid error policy, expected `stop`, `continue` or `skip`";
+
+pub const HELP_METHOD: &str = "requests must start with a method, like `get`";
+pub const HELP_URL: &str = "the url must follow the method, separated by a space";
+pub const HELP_ATTRIBUTE: &str = "attributes must be written as `key=value`";
+pub const HELP_SELECTOR: &str = "a JSONPath selector must follow `^`, like `^$.id`";
+pub const HELP_ACCEPT: &str = "accepted statuses must be codes or classes, like `!404,5xx`";
+pub const HELP_SETTING: &str =
+	"request settings are `timeout`, `retries`, `retry_on` and `dedupe`, like `@timeout=5s`";
+pub const HELP_ASSERT: &str =
+	"assertions are written as `%status=200`, `%$.id!=0`, `%$.name~=\"^B\"` or `%headers.etag`";
+pub const HELP_BOOLEAN: &str = "booleans must be `true` or `false`";
+pub const HELP_DURATION: &str = "durations must be written with a unit, like `500ms`, `5s` or `1m`";
+pub const HELP_SAVE_AS: &str = "a variable name must follow `>`, like `>token`";
+pub const HELP_UNTERMINATED_STRING: &str = "add a closing `\"`, or escape it with `\\\"`";
+pub const HELP_UNTERMINATED_RAW_BODY: &str = "raw JSON bodies must end with `-#`";
+pub const HELP_UNCLOSED_DELIMITER: &str = "add a closing `}` to the nested request";
+pub const HELP_ENV: &str =
+	"environment variables are written as `${NAME}`, or `${NAME:-default}` with a default";
+pub const HELP_UNDEFINED_ENV: &str =
+	"set the variable, or provide a default like `${NAME:-default}`";
+pub const HELP_DIRECTIVE: &str =
+	"directives are `@base` and `@header`, like `@base https://example.com`";
+pub const HELP_HEADER_DIRECTIVE: &str =
+	"a header must follow `@header`, like `@header authorization=\"Bearer xxx\"`";
+pub const HELP_BASE_URL: &str =
+	"a base url must follow `@base`, like `@base https://example.com`, without nested requests";
+pub const HELP_RELATIVE_URL: &str =
+	"set a base url with `@base https://example.com`, `--base-url` or a profile";
+pub const HELP_LET: &str =
+	"variables are assigned a value or a request, like `let name = \"value\"` or `let id = {get https://example.com^$.id}`";
+pub const HELP_VARIABLE_NAME: &str =
+	"variable names can only contain letters, digits and `_`, and can't start with a digit";
+pub const HELP_UNKNOWN_VARIABLE: &str =
+	"assign the variable with `let` or save a response with `>name` before it, or write a literal `$` as `\\$`";
+pub const HELP_UNEXPECTED_CLOSE_DELIMITER: &str = "wrap values containing `}` in quotes";
+pub const HELP_UNEXPECTED_TOKEN: &str =
+	"wrap values containing spaces in quotes, or separate requests with `;`";
+pub const HELP_MULTIPLE_REQUESTS: &str = "run sequential requests from a file with `-f`";
+pub const HELP_MULTIPLE_EACH: &str = "nest the other `each` request in a request of its own";
+pub const HELP_HEADER_NAME: &str = "header names can only contain letters, digits and `-`";
+pub const HELP_UNKNOWN_METHOD: &str =
+	"use a method keyword like `get`, or write custom methods in uppercase, like `PURGE`";
 
 pub const ERR_UNKNOWN_METHOD: &str = "Unknown request method";
@@ -0,0 +1,30 @@
+use crate::constants;
+use reqwest::header::HeaderMap;
+
+/// Settings applied to every request, unless the request sets them itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestDefaults {
+	/// Url that relative urls, starting with `/`, are resolved against.
+	pub base_url: Option<String>,
+
+	/// Headers sent with every request, unless the request sends
+	/// a header with the same name.
+	pub headers: HeaderMap,
+}
+
+impl RequestDefaults {
+	/// Resolve `url` against the base url, if it's relative and
+	/// a base url is set. Otherwise `url` is returned as it is.
+	pub fn resolve_url(&self, url: &str) -> String {
+		match &self.base_url {
+			Some(base) if url.starts_with(constants::URL_PATH_START) => {
+				format!(
+					"{}{}",
+					base.trim_end_matches(constants::URL_PATH_START),
+					url
+				)
+			}
+			_ => String::from(url),
+		}
+	}
+}
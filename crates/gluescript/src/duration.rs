@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Parse a duration written as a number followed by a unit, like `500ms`,
+/// `5s` or `1m`. Numbers without a unit are seconds.
+///
+/// `None` is returned if `value` is not a valid duration.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+	let value = value.trim();
+
+	// Split the number from its unit.
+	let unit_start = value
+		.find(|x: char| !x.is_ascii_digit() && x != '.')
+		.unwrap_or(value.len());
+	let amount = value[..unit_start].parse::<f64>().ok()?;
+
+	let seconds = match &value[unit_start..] {
+		"ms" => amount / 1000.0,
+		"" | "s" => amount,
+		"m" => amount * 60.0,
+		_ => return None,
+	};
+
+	Duration::try_from_secs_f64(seconds).ok()
+}
@@ -0,0 +1,127 @@
+use crate::Span;
+use colored::*;
+use std::{error::Error, fmt};
+
+/// An error found while parsing or resolving gluescript source.
+///
+/// Besides the `message`, it holds the position of the offending text,
+/// so it can be displayed with the source line it was found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+	/// Short description of the error.
+	pub message: String,
+
+	/// Optional hint on how to fix the error.
+	pub help: Option<String>,
+
+	/// Position of the offending text in the source.
+	pub span: Span,
+
+	/// Line of the source the span starts at, starting from 1.
+	pub line: usize,
+
+	/// Column of the source the span starts at, starting from 1.
+	pub column: usize,
+
+	/// Full text of the source line the span starts at.
+	pub line_text: String,
+
+	/// Number of chars of `line_text` to underline, starting from `column`.
+	pub length: usize,
+}
+
+impl ParseError {
+	/// Create a new `ParseError` located at `span` in `source`.
+	pub fn new(message: &str, span: Span, source: &str) -> Self {
+		// Spans can point right after the end of source, for instance
+		// when a closing quote is missing.
+		let start = span.start.min(source.len());
+		let end = span.end.clamp(start, source.len());
+
+		let line_start = source[..start].rfind('\n').map_or(0, |x| x + 1);
+		let line_end = source[start..]
+			.find('\n')
+			.map_or(source.len(), |x| start + x);
+
+		ParseError {
+			message: String::from(message),
+			help: None,
+			span,
+			line: source[..start].matches('\n').count() + 1,
+			column: source[line_start..start].chars().count() + 1,
+			line_text: String::from(source[line_start..line_end].trim_end_matches('\r')),
+			length: source[start..end.min(line_end)].chars().count().max(1),
+		}
+	}
+
+	/// Attach a hint to the error.
+	pub fn with_help(mut self, help: &str) -> Self {
+		self.help = Some(String::from(help));
+		self
+	}
+
+	/// Render the error along with the offending source line,
+	/// underlined with carets:
+	///
+	/// ```text
+	/// error: Failed to resolve value from attribute
+	///  --> 3:10
+	///   |
+	/// 3 | ~username=
+	///   |           ^
+	///   = help: attributes must be written as `key=value`
+	/// ```
+	pub fn diagnostic(&self) -> String {
+		let line_number = self.line.to_string();
+		let gutter = " ".repeat(line_number.len());
+
+		// Tabs are kept in the padding, so carets stay aligned with
+		// the source line whatever the tab width is.
+		let padding: String = self
+			.line_text
+			.chars()
+			.take(self.column - 1)
+			.map(|x| if x == '\t' { '\t' } else { ' ' })
+			.collect();
+
+		let mut output = format!(
+			"{}{} {}\n{}{} {}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+			"error".red().bold(),
+			":".bold(),
+			self.message.bold(),
+			gutter,
+			"-->".blue().bold(),
+			self.line,
+			self.column,
+			gutter,
+			"|".blue().bold(),
+			line_number.blue().bold(),
+			"|".blue().bold(),
+			self.line_text,
+			gutter,
+			"|".blue().bold(),
+			padding,
+			"^".repeat(self.length).red().bold(),
+		);
+
+		if let Some(help) = &self.help {
+			output.push_str(&format!(
+				"\n{} {} {} {}",
+				gutter,
+				"=".blue().bold(),
+				"help:".bold(),
+				help
+			));
+		}
+
+		output
+	}
+}
+
+impl Error for ParseError {}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} at {}:{}", self.message, self.line, self.column)
+	}
+}
@@ -0,0 +1,512 @@
+use crate::{constants, ParseError, Span};
+
+/// Kind of a single gluescript token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+	/// A run of chars without any special meaning, like a method,
+	/// a piece of url or an attribute key.
+	Word(String),
+
+	/// Literal text found between quotes. Escape sequences are
+	/// already resolved.
+	Text(String),
+
+	/// Content of a raw JSON body, delimited by `~#-` and `-#`.
+	RawBody(String),
+
+	/// An environment variable, written as `${NAME}` or `${NAME:-default}`.
+	Env {
+		name: String,
+		default: Option<String>,
+	},
+
+	/// A saved variable, written as `$name`.
+	Variable(String),
+
+	/// One or more consecutive whitespace chars, new lines included,
+	/// along with any comment between them.
+	Whitespace,
+
+	/// `"`, opening or closing a quoted value.
+	Quote,
+
+	/// `{`, opening a nested request.
+	OpenDelimiter,
+
+	/// `}`, closing a nested request.
+	CloseDelimiter,
+
+	/// `^`, JSONPath result selector operator.
+	Selector,
+
+	/// `~`, body attribute operator.
+	Body,
+
+	/// `*`, header attribute operator.
+	Header,
+
+	/// `>`, save response as variable operator.
+	SaveAs,
+
+	/// `?`, query parameter operator.
+	Query,
+
+	/// `!`, accepted statuses operator.
+	Accept,
+
+	/// `@`, request setting operator.
+	Setting,
+
+	/// `%`, response assertion operator.
+	Assert,
+
+	/// `=`, separating attribute keys from values.
+	Equals,
+
+	/// `;`, separating sequential requests.
+	Separator,
+}
+
+/// A token produced by the `Lexer`, along with the
+/// position of the text it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+	pub kind: TokenKind,
+	pub span: Span,
+}
+
+/// The lexer has to know if it is reading from quoted text
+/// or not, as special chars are literal between quotes,
+/// with the only exception of nested requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+	Plain,
+	Quoted,
+}
+
+/// Split a gluescript source in a flat list of `Token`.
+///
+/// The lexer keeps a stack of modes: each `{` pushes a plain mode and
+/// each `"` toggles the quoted mode, so nested requests can also be
+/// placed between quotes.
+pub struct Lexer<'a> {
+	/// Source being tokenized.
+	source: &'a str,
+
+	/// Byte offset of the next char to read.
+	cursor: usize,
+
+	/// Stack of modes, the last one is the current. Each mode is paired
+	/// with the offset of the char that opened it.
+	modes: Vec<(Mode, usize)>,
+
+	/// Tokens produced so far.
+	tokens: Vec<Token>,
+}
+
+impl<'a> Lexer<'a> {
+	/// Create a new `Lexer` for `source`.
+	pub fn new(source: &'a str) -> Self {
+		Lexer {
+			source,
+			cursor: 0,
+			modes: vec![(Mode::Plain, 0)],
+			tokens: vec![],
+		}
+	}
+
+	/// Consume the lexer and return all tokens in `source`.
+	/// Err is returned on unterminated quotes, raw bodies or nested requests.
+	pub fn tokenize(mut self) -> Result<Vec<Token>, ParseError> {
+		while let Some(char) = self.peek() {
+			match self.mode() {
+				Mode::Quoted => self.quoted(char)?,
+				Mode::Plain => self.plain(char)?,
+			}
+		}
+
+		// Every mode pushed must have been popped before the end of the source.
+		// The error points to the char that opened the mode.
+		let (mode, start) = *self.modes.last().unwrap();
+		let span = Span::new(start, start + 1);
+
+		match mode {
+			Mode::Quoted => {
+				Err(
+					ParseError::new(constants::ERR_UNTERMINATED_STRING, span, self.source)
+						.with_help(constants::HELP_UNTERMINATED_STRING),
+				)
+			}
+			Mode::Plain if self.modes.len() > 1 => {
+				Err(
+					ParseError::new(constants::ERR_UNCLOSED_DELIMITER, span, self.source)
+						.with_help(constants::HELP_UNCLOSED_DELIMITER),
+				)
+			}
+			Mode::Plain => Ok(self.tokens),
+		}
+	}
+
+	/// Read the next token in plain mode, starting at `char`.
+	fn plain(&mut self, char: char) -> Result<(), ParseError> {
+		let start = self.cursor;
+
+		let kind = match char {
+			// Comments are read as whitespace, along with the whitespace
+			// around them. A `#` only starts a comment at the start of
+			// the source, after whitespace or after a separator, so urls
+			// can contain it.
+			x if x.is_whitespace() || (x == constants::COMMENT && self.at_comment_start()) => {
+				self.whitespace();
+				TokenKind::Whitespace
+			}
+
+			constants::QUOTE => {
+				self.advance();
+				self.modes.push((Mode::Quoted, start));
+				TokenKind::Quote
+			}
+
+			constants::OPEN_DELIMITER => {
+				self.advance();
+				self.modes.push((Mode::Plain, start));
+				TokenKind::OpenDelimiter
+			}
+
+			constants::CLOSE_DELIMITER => {
+				// The outermost mode can't be closed.
+				if self.modes.len() == 1 {
+					return Err(ParseError::new(
+						constants::ERR_UNEXPECTED_CLOSE_DELIMITER,
+						Span::new(start, start + 1),
+						self.source,
+					)
+					.with_help(constants::HELP_UNEXPECTED_CLOSE_DELIMITER));
+				}
+
+				self.advance();
+				self.modes.pop();
+				TokenKind::CloseDelimiter
+			}
+
+			constants::BODY_OPERATOR if self.rest().starts_with(constants::RAW_BODY_START) => {
+				self.raw_body()?
+			}
+
+			_ if self.rest().starts_with(constants::ENV_START) => self.env()?,
+
+			_ if self.at_variable() => self.variable(),
+
+			_ => match operator_kind(char) {
+				Some(kind) => {
+					self.advance();
+					kind
+				}
+
+				// Anything else is part of a word, which ends on the first
+				// whitespace, special char, environment or saved variable.
+				// A saved variable can be kept literal with `\$name`.
+				None => {
+					let mut word = String::new();
+
+					while let Some(x) = self.peek() {
+						if x.is_whitespace()
+							|| is_special(x) || self.rest().starts_with(constants::ENV_START)
+							|| self.at_variable()
+						{
+							break;
+						}
+
+						// The escape is dropped, along with the `$` it escapes.
+						if x == constants::ESCAPE && self.at_escaped_variable() {
+							self.advance();
+							word.push(self.advance().unwrap());
+							continue;
+						}
+
+						self.advance();
+						word.push(x);
+					}
+
+					TokenKind::Word(word)
+				}
+			},
+		};
+
+		self.push(kind, start);
+		Ok(())
+	}
+
+	/// Read the next token in quoted mode, starting at `char`.
+	fn quoted(&mut self, char: char) -> Result<(), ParseError> {
+		let start = self.cursor;
+
+		let kind = match char {
+			constants::QUOTE => {
+				self.advance();
+				self.modes.pop();
+				TokenKind::Quote
+			}
+
+			// Nested requests, environment and saved variables are
+			// allowed between quotes too.
+			constants::OPEN_DELIMITER => {
+				self.advance();
+				self.modes.push((Mode::Plain, start));
+				TokenKind::OpenDelimiter
+			}
+
+			_ if self.rest().starts_with(constants::ENV_START) => self.env()?,
+
+			_ if self.at_variable() => self.variable(),
+
+			_ => {
+				let mut text = String::new();
+
+				while let Some(x) = self.peek() {
+					match x {
+						constants::QUOTE | constants::OPEN_DELIMITER => break,
+						_ if self.rest().starts_with(constants::ENV_START) => break,
+						_ if self.at_variable() => break,
+
+						// Escaped chars are always taken literally. If the escape
+						// is the last char, the missing closing quote is reported
+						// at the end of source.
+						constants::ESCAPE => {
+							self.advance();
+							if let Some(escaped) = self.advance() {
+								text.push(escaped);
+							}
+						}
+
+						_ => {
+							self.advance();
+							text.push(x);
+						}
+					}
+				}
+
+				TokenKind::Text(text)
+			}
+		};
+
+		self.push(kind, start);
+		Ok(())
+	}
+
+	/// Read whitespace and comments, until anything else is found.
+	fn whitespace(&mut self) {
+		loop {
+			self.advance_while(|x| x.is_whitespace());
+
+			match self.peek() {
+				Some(constants::COMMENT) => self.advance_while(|x| x != '\n'),
+				_ => return,
+			}
+		}
+	}
+
+	/// Read an environment variable, written as `${NAME}` or `${NAME:-default}`.
+	/// Its value is read by the parser.
+	fn env(&mut self) -> Result<TokenKind, ParseError> {
+		let start = self.cursor;
+		let content_start = start + constants::ENV_START.len();
+
+		let content = match self.source[content_start..].find(constants::ENV_END) {
+			None => {
+				return Err(ParseError::new(
+					constants::ERR_UNCLOSED_ENV,
+					Span::new(start, content_start),
+					self.source,
+				)
+				.with_help(constants::HELP_ENV))
+			}
+			Some(length) => &self.source[content_start..content_start + length],
+		};
+
+		self.cursor = content_start + content.len() + constants::ENV_END.len_utf8();
+
+		match env_parts(content) {
+			None => Err(ParseError::new(
+				constants::ERR_INVALID_ENV_NAME,
+				Span::new(start, self.cursor),
+				self.source,
+			)
+			.with_help(constants::HELP_ENV)),
+			Some((name, default)) => Ok(TokenKind::Env {
+				name: String::from(name),
+				default: default.map(String::from),
+			}),
+		}
+	}
+
+	/// Check if a `#` at the cursor starts a comment, being the first token
+	/// of the source or following a separator. After whitespace, comments
+	/// are read along with it.
+	fn at_comment_start(&self) -> bool {
+		match self.tokens.last() {
+			None => true,
+			Some(x) => x.kind == TokenKind::Separator,
+		}
+	}
+
+	/// Check if a saved variable starts at the cursor: a `$` followed by
+	/// a letter or `_`, so JSONPath selectors like `$.id` are not variables.
+	/// A `$` right after a letter, digit or `_` is part of the word, like
+	/// in `a$b`.
+	fn at_variable(&self) -> bool {
+		let after_word = self.source[..self.cursor]
+			.chars()
+			.next_back()
+			.is_some_and(is_name_char);
+
+		!after_word && is_variable(self.rest())
+	}
+
+	/// Check if an escaped saved variable, like `\$name`, starts at the cursor.
+	fn at_escaped_variable(&self) -> bool {
+		self.rest()
+			.strip_prefix(constants::ESCAPE)
+			.is_some_and(is_variable)
+	}
+
+	/// Read a saved variable, written as `$name`. Its value is read
+	/// from the heap when the request is executed.
+	fn variable(&mut self) -> TokenKind {
+		self.advance();
+		let start = self.cursor;
+		self.advance_while(is_name_char);
+
+		TokenKind::Variable(String::from(&self.source[start..self.cursor]))
+	}
+
+	/// Read a raw JSON body. Its content is never tokenized,
+	/// so it can contain any char.
+	fn raw_body(&mut self) -> Result<TokenKind, ParseError> {
+		let content_start = self.cursor + constants::RAW_BODY_START.len();
+
+		match self.source[content_start..].find(constants::RAW_BODY_END) {
+			None => Err(ParseError::new(
+				constants::ERR_UNTERMINATED_RAW_BODY,
+				Span::new(self.cursor, content_start),
+				self.source,
+			)
+			.with_help(constants::HELP_UNTERMINATED_RAW_BODY)),
+			Some(length) => {
+				self.cursor = content_start + length + constants::RAW_BODY_END.len();
+				Ok(TokenKind::RawBody(String::from(
+					&self.source[content_start..content_start + length],
+				)))
+			}
+		}
+	}
+
+	/// Push a new token of `kind`, spanning from `start` to the cursor.
+	fn push(&mut self, kind: TokenKind, start: usize) {
+		self.tokens.push(Token {
+			kind,
+			span: Span::new(start, self.cursor),
+		});
+	}
+
+	/// Current lexer mode.
+	fn mode(&self) -> Mode {
+		self.modes.last().unwrap().0
+	}
+
+	/// Remaining source, starting from the cursor.
+	fn rest(&self) -> &'a str {
+		&self.source[self.cursor..]
+	}
+
+	/// Get the next char without consuming it.
+	fn peek(&self) -> Option<char> {
+		self.rest().chars().next()
+	}
+
+	/// Consume and return the next char.
+	fn advance(&mut self) -> Option<char> {
+		let char = self.peek()?;
+		self.cursor += char.len_utf8();
+		Some(char)
+	}
+
+	/// Consume chars as long as `predicate` holds.
+	fn advance_while(&mut self, predicate: impl Fn(char) -> bool) {
+		while let Some(char) = self.peek() {
+			if !predicate(char) {
+				break;
+			}
+			self.cursor += char.len_utf8();
+		}
+	}
+}
+
+/// Tokenize `source` in a single call.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+	Lexer::new(source).tokenize()
+}
+
+/// Get the single-char token kind of `char`, if any.
+fn operator_kind(char: char) -> Option<TokenKind> {
+	match char {
+		constants::SELECTOR_OPERATOR => Some(TokenKind::Selector),
+		constants::BODY_OPERATOR => Some(TokenKind::Body),
+		constants::HEADER_OPERATOR => Some(TokenKind::Header),
+		constants::SAVE_AS_OPERATOR => Some(TokenKind::SaveAs),
+		constants::QUERY_OPERATOR => Some(TokenKind::Query),
+		constants::ACCEPT_OPERATOR => Some(TokenKind::Accept),
+		constants::SETTING_OPERATOR => Some(TokenKind::Setting),
+		constants::ASSERT_OPERATOR => Some(TokenKind::Assert),
+		constants::EQUALS => Some(TokenKind::Equals),
+		constants::SEPARATOR => Some(TokenKind::Separator),
+		_ => None,
+	}
+}
+
+/// Split the `content` of an environment variable, between `${` and `}`,
+/// in its name and default value.
+///
+/// `None` is returned if the name is not valid. Names follow the same
+/// rules of shell variables.
+pub fn env_parts(content: &str) -> Option<(&str, Option<&str>)> {
+	let (name, default) = match content.split_once(constants::ENV_DEFAULT) {
+		None => (content, None),
+		Some((name, default)) => (name, Some(default)),
+	};
+
+	let is_valid = name.starts_with(|x: char| x.is_ascii_alphabetic() || x == '_')
+		&& name.chars().all(|x| x.is_ascii_alphanumeric() || x == '_');
+
+	match is_valid {
+		true => Some((name, default)),
+		false => None,
+	}
+}
+
+/// Check if `name` can be used as a saved variable, written as `$name`.
+/// Names follow the same rules of environment variables.
+pub fn is_variable_name(name: &str) -> bool {
+	env_parts(name) == Some((name, None))
+}
+
+/// Whether `char` ends a word.
+fn is_special(char: char) -> bool {
+	matches!(
+		char,
+		constants::QUOTE | constants::OPEN_DELIMITER | constants::CLOSE_DELIMITER
+	) || operator_kind(char).is_some()
+}
+
+/// Whether `char` can be part of a saved variable name.
+fn is_name_char(char: char) -> bool {
+	char.is_ascii_alphanumeric() || char == '_'
+}
+
+/// Check if `source` starts with a saved variable: a `$` followed by
+/// a letter or `_`.
+fn is_variable(source: &str) -> bool {
+	source
+		.strip_prefix(constants::VARIABLE_START)
+		.is_some_and(|x| x.starts_with(|x: char| x.is_ascii_alphabetic() || x == '_'))
+}
@@ -1,13 +1,39 @@
 pub mod constants;
 
+pub mod span;
+pub use span::Span;
+
+pub mod errors;
+pub use errors::ParseError;
+
+pub mod lexer;
+pub use lexer::{Lexer, Token, TokenKind};
+
+pub mod ast;
+pub use ast::{
+	Comparison, Directive, DirectiveKind, Operator, OperatorKind, Request, Script, Value, ValuePart,
+};
+
+pub mod parser;
+pub use parser::{parse, parse_request, Parser};
+
 pub mod node;
 pub use node::GlueNode;
 
-pub mod request_body;
-pub use request_body::{RequestBody, RequestBodyType};
+pub mod response;
+pub use response::Response;
+
+pub mod duration;
+pub use duration::parse_duration;
+
+pub mod assertion;
+pub use assertion::Assertion;
 
-pub mod utils;
-pub use utils::{exclude_quoted_text, trim_and_remove_quotes};
+pub mod status;
+pub use status::StatusPolicy;
 
-pub mod serialized;
-pub use serialized::Serialized;
+pub mod defaults;
+pub use defaults::RequestDefaults;
+
+pub mod request_body;
+pub use request_body::{RequestBody, RequestBodyType};
@@ -1,32 +1,35 @@
 use crate::{
-	constants, exclude_quoted_text,
-	utils::{
-		extract_and_mask_quoted_text, get_raw_json_body, is_value_a_quoted_reference,
-		quoted_reference_to_value, remove_serialization_placeholders, resolve_key_and_value,
-	},
-	RequestBody, RequestBodyType, Serialized,
+	ast::{Comparison, OperatorKind, Request, Value, ValuePart},
+	constants, parse_duration,
+	parser::parse_request,
+	Assertion, ParseError, RequestBody, RequestBodyType, RequestDefaults, Response, Span,
+	StatusPolicy,
 };
 use colored::*;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use std::{
-	collections::HashMap,
-	sync::{Arc, Mutex},
+use regex::Regex;
+use reqwest::{
+	header::{HeaderMap, HeaderName, HeaderValue},
+	Method,
 };
+use std::{collections::HashMap, mem, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
 
 /// Main structure for holding request data.
 ///
-/// Its components are calculated starting from its `command`,
-/// over which an iterative parsing process is done to resolve all other
-/// parts.
+/// Its components are calculated starting from its `request`,
+/// the AST parsed from `command`.
 ///
 /// Each `GlueNode` instance may contain other instances underneath as
 /// dependencies that have to be resolved and executed before the main one
 /// is resolved and executed.
 #[derive(Debug, Clone)]
 pub struct GlueNode {
-	/// `Serialized` value associated at creation time and used
-	/// to calculate other struct data after its parsing.
-	pub command: Serialized,
+	/// Source text of the request, as written by the user.
+	pub command: String,
+
+	/// Whole source the request was parsed from, shared with all
+	/// other nodes parsed from it. Spans of `request` point into it.
+	pub source: Arc<str>,
 
 	/// String value associated after `command` parsing.
 	/// Similar use of `command`, but it is intended to
@@ -34,6 +37,10 @@ pub struct GlueNode {
 	/// in change of them).
 	pub predicate: String,
 
+	/// Parsed request. Nested requests are moved into
+	/// `dependencies` and replaced with a `ValuePart::Dependency`.
+	pub request: Request,
+
 	/// Request HTTP method.
 	pub method: String,
 
@@ -56,6 +63,14 @@ pub struct GlueNode {
 	/// for the request to be resolved.
 	pub dependencies: Vec<Arc<Mutex<GlueNode>>>,
 
+	/// Index of the dependency nested with `each`, if any. The request
+	/// is run once for each element of its result, instead of once.
+	pub each_dependency: Option<usize>,
+
+	/// Whether the `GlueNode` is nested with `each`, so its result
+	/// is the list of all selected values.
+	pub each: bool,
+
 	/// Depth of the `GlueNode` in the dep tree:
 	/// 0 if root node, 1 if first dependency in the graph,
 	/// 2 if dependency of another dependency etc..
@@ -64,206 +79,271 @@ pub struct GlueNode {
 	/// Result of `GlueNode` http execution and response parsing.
 	pub result: String,
 
+	/// Whole response of the http request.
+	/// `None` if not executed yet, or if the result is read from memory.
+	pub response: Option<Response>,
+
+	/// Response statuses accepted as a success, other than 2xx and 3xx.
+	pub accept: StatusPolicy,
+
+	/// Maximum time the request can take, from connecting to reading the
+	/// whole response. If `None`, the client timeout is used.
+	pub timeout: Option<Duration>,
+
+	/// Maximum number of retries of the request. If `None`, the
+	/// runner policy is used.
+	pub retries: Option<u32>,
+
+	/// Response statuses that are retried. If `None`, the
+	/// runner policy is used.
+	pub retry_on: Option<StatusPolicy>,
+
+	/// Assertions on the response. If any, they are checked in place
+	/// of the response status.
+	pub assertions: Vec<Assertion>,
+
+	/// Whether identical requests in the same run share a single response.
+	/// If `None`, only requests with safe methods, like `get`, are shared.
+	pub dedupe: Option<bool>,
+
 	/// Key to be used to save the `GlueNode` result.
 	/// Response will be ephemeral if `None` is provided.
 	pub save_as: Option<String>,
 }
 
 impl GlueNode {
-	/// Create a new `GlueNode` instance from a `command` literal
-	/// and a `depth`, indicating its position in a `GlueNode` tree
-	/// structure.
-	pub fn new(command: &String, depth: usize) -> Self {
-		GlueNode {
-			command: Serialized::new(command.to_owned()),
+	/// Create a new `GlueNode` instance from a parsed `request`, the `source`
+	/// it was parsed from and a `depth`, indicating its position in a
+	/// `GlueNode` tree structure.
+	///
+	/// Nested requests are recursively turned into dependencies.
+	pub fn new(source: Arc<str>, request: Request, depth: usize) -> Self {
+		let mut node = GlueNode {
+			command: String::from(&source[request.span.start..request.span.end]),
+			source,
 			predicate: String::from(""),
+			request,
 			method: String::from(""),
 			url: String::from(""),
 			headers: None,
 			body: None,
 			result_selector: String::from(""),
 			dependencies: Vec::new(),
+			each_dependency: None,
+			each: false,
 			depth,
 			result: String::from(""),
+			response: None,
+			accept: StatusPolicy::default(),
+			timeout: None,
+			retries: None,
+			retry_on: None,
+			assertions: vec![],
+			dedupe: None,
 			save_as: None,
-		}
+		};
+
+		node.build_tree_recursive();
+		node
 	}
 
 	/// Create a `GlueNode` instance starting from a `command`.
 	/// Returns an `Err` if something goes wrong with the parsing.
-	pub fn from_string(command: &String) -> Result<Self, String> {
-		let mut root_node = GlueNode::new(command, 0);
-
-		// Parse the `root_node` command to build its predicate.
+	pub fn from_string(command: &str) -> Result<Self, ParseError> {
+		// Parse the command to build the root node.
 		// Propagate Err on fail.
-		root_node.build_tree_recursive()?;
-		Ok(root_node)
+		let request = parse_request(command)?;
+		Ok(GlueNode::new(Arc::from(command), request, 0))
 	}
 
-	/// Recursively parse `self.command` up until a closing
-	/// delimiter is encountered.
-	///
-	/// Call itself when a new open delimiter is encountered.
+	/// Move every nested request of `self.request` into a dependency
+	/// `GlueNode`, leaving a `ValuePart::Dependency` in place of it.
 	///
-	/// Return a wrapped usize to the function caller to allow it to
-	/// determine at which point of the `self.command` the dependency was closed.
-	fn build_tree_recursive(self: &mut Self) -> Result<usize, String> {
-		// This holds an index to the next dependency closing delimiter while
-		// iterating over chars of `self.command`.
-		// This index is used to skip parsing if iterating in a `command` substr
-		// that is part of a dependency, because its parsing will be already done
-		// by another iteration of `build_tree_recursive()`
-		let mut skip_till: usize = 0;
-
-		// Parse the command, char by char.
-		for (i, char) in &mut self.command.serialized().chars().enumerate() {
-			// Skip parsing if cursor is in a dependency
-			if skip_till > 0 && i <= skip_till + 1 {
-				continue;
-			}
+	/// Each dependency does the same with its own nested requests,
+	/// with an incremented depth.
+	fn build_tree_recursive(&mut self) {
+		let source = Arc::clone(&self.source);
+		let span = self.request.span;
+		let end = span.end;
+
+		// Byte offset of `source` up until `self.predicate` has been built.
+		let mut cursor = span.start;
+
+		// Values are walked in source order, so dependencies are always
+		// in the same order of `{}` placeholders in the predicate.
+		for value in self.request.values_mut() {
+			for part in value.parts.iter_mut() {
+				if !matches!(part, ValuePart::Nested { .. }) {
+					continue;
+				}
 
-			match char {
-				// If an open delimiter is hit the control flow must be passed
-				// to another iteration of the function, so the predicate
-				// can be parsed for the dependency.
-				constants::OPEN_DELIMITER => {
-					// Create a new `GlueNode` instance from a `command`
-					// based on a substring of `self.command`, starting from
-					// the current cursor index. Depth is incrementally assigned.
-					let mut dependency = GlueNode::new(
-						&self.command.serialized()[(i + 1)..].to_string(),
-						self.depth + 1,
-					);
-
-					// build_tree_recursive() is called for the newly created dependency
-					// and its result is used to know where is the next closing delimiter.
-					// Err is propagated on dependency parsing failure.
-					skip_till = dependency.build_tree_recursive()? + i;
-
-					// Dependency is pushed into the `dependencies` collection.
-					self.dependencies.push(Arc::new(Mutex::new(dependency)));
+				let index = self.dependencies.len();
 
+				if let ValuePart::Nested {
+					request,
+					span,
+					each,
+				} = mem::replace(part, ValuePart::Dependency(index))
+				{
 					// A `{}` is added to `self.predicate` so it will be possible
-					// to replace it afterwards with the actual dependency
-					// result.
-					self.predicate.push_str("{}");
-				}
+					// to replace it afterwards with the actual dependency result.
+					// Requests of default headers are written out of the request,
+					// so they are not part of the predicate.
+					if span.start >= cursor && span.end <= end {
+						self.predicate.push_str(&source[cursor..span.start]);
+						self.predicate.push_str("{}");
+						cursor = span.end;
+					}
 
-				// If a closing delimiter is hit, the function is terminated
-				// and the current cursor index is returned to the caller.
-				constants::CLOSE_DELIMITER => {
-					return Ok(i);
-				}
+					let mut dependency =
+						GlueNode::new(Arc::clone(&source), *request, self.depth + 1);
 
-				// In any other case, the char is added to `self.predicate`.
-				_ => self.predicate.push(char),
+					if each {
+						dependency.each = true;
+						self.each_dependency = Some(index);
+					}
+
+					self.dependencies.push(Arc::new(Mutex::new(dependency)));
+				}
 			}
 		}
 
-		// If this statement is reached, then the `GlueNode`
-		// that is executing this function is not a dependency
-		// of another node, so 0 is returned.
-		Ok(0)
+		self.predicate.push_str(&source[cursor..span.end]);
 	}
 
-	/// Parse the predicate and tries to resolve other parts of `GlueNode`.
+	/// Resolve all parts of `GlueNode` from the parsed request.
 	/// Return Err on any resolve failure.
-	pub fn resolve_predicate(self: &mut Self) -> Result<(), String> {
+	pub fn resolve_predicate(&mut self) -> Result<(), ParseError> {
 		self.resolve_method()?;
 		self.resolve_url()?;
-		self.resolve_selector();
+		self.resolve_selector()?;
 		self.resolve_save_as();
+		self.resolve_accept()?;
+		self.resolve_settings()?;
+		self.resolve_assertions()?;
 		self.resolve_headers()?;
 		self.resolve_body()?;
 
 		Ok(())
 	}
 
-	/// Resolve http request method from `self.predicate`.
-	/// Error is returned is no method is found.
-	fn resolve_method(self: &mut Self) -> Result<(), String> {
-		// Method must always be the first part of the predicate, followed
-		// by a white space.
-		self.method = match self.predicate.trim().split(' ').nth(0) {
-			None => return Err(String::from(constants::ERR_UNRESOLVED_METHOD)),
-			Some(x) => x.to_string().replace("\n", ""),
-		};
+	/// Resolve http request method from `self.request`.
+	/// Methods are either lowercase keywords, or custom methods written
+	/// in uppercase, like `PURGE`, which are sent as they are.
+	/// Error is returned if the method is unknown.
+	fn resolve_method(&mut self) -> Result<(), ParseError> {
+		let method = &self.request.method;
+
+		match method.as_str() {
+			constants::GET
+			| constants::POST
+			| constants::PUT
+			| constants::PATCH
+			| constants::DELETE
+			| constants::HEAD
+			| constants::OPTIONS
+			| constants::TRACE
+			| constants::REQ
+			| constants::LET => (),
+			x if is_custom_method(x) => (),
+			_ => {
+				return Err(self
+					.error(constants::ERR_UNKNOWN_METHOD, self.method_span())
+					.with_help(constants::HELP_UNKNOWN_METHOD))
+			}
+		}
 
+		self.method = method.clone();
 		Ok(())
 	}
 
-	/// Resolve http request canonical url from `self.predicate`.
-	/// Error is returned is no url is found.
-	fn resolve_url(self: &mut Self) -> Result<(), String> {
-		let clean_predicate = remove_serialization_placeholders(&self.predicate);
-		// Url should always be the second token of the predicate,
-		// preceded by a space, but not necessarily followed by it.
-		let resource = match clean_predicate.trim().split(' ').nth(1) {
-			None => return Err(String::from(constants::ERR_UNRESOLVED_URL)),
-			Some(x) => x,
-		};
+	/// Position of the request method in `self.source`.
+	pub fn method_span(&self) -> Span {
+		// Method is always at the very start of the request.
+		let start = self.request.span.start;
+		Span::new(start, start + self.request.method.len())
+	}
 
-		// Exclude every other operator from the url
-		self.url = match exclude_quoted_text(resource.to_string())
-			.split(['^', '~', '*'])
-			.nth(0)
-		{
-			None => return Err(String::from(constants::ERR_UNRESOLVED_URL)),
-			Some(x) => x.to_string().replace("\n", ""),
-		};
+	/// Resolve http request canonical url from `self.request`.
+	/// Query parameters are percent-encoded and appended to the
+	/// query string already in the url, if any.
+	/// Error is returned if a dependency is not resolved yet.
+	fn resolve_url(&mut self) -> Result<(), ParseError> {
+		let mut url = self.resolve_value(&self.request.url)?;
+		let mut query = vec![];
+
+		for operator in &self.request.operators {
+			if let OperatorKind::Query { key, value } = &operator.kind {
+				query.push(format!(
+					"{}={}",
+					percent_encode(key),
+					percent_encode(&self.resolve_value(value)?)
+				));
+			}
+		}
 
-		Ok(())
-	}
+		if !query.is_empty() {
+			// The fragment, if any, must stay at the end of the url.
+			let fragment = match url.find('#') {
+				Some(x) => url.split_off(x),
+				None => String::new(),
+			};
 
-	/// Resolve request response JSONPath selector from predicate.
-	/// Empty string is returned if no selector is found.
-	fn resolve_selector(self: &mut Self) -> () {
-		self.result_selector = match exclude_quoted_text(String::from(&self.predicate))
-			.split('^')
-			.nth(1)
-		{
-			None => "".to_string(),
-			Some(x) => x.to_string(),
-		};
-	}
+			match url.find('?') {
+				None => url.push('?'),
+				Some(_) if !url.ends_with('?') && !url.ends_with('&') => url.push('&'),
+				Some(_) => (),
+			}
 
-	/// Resolve http request headers `self.predicate`.
-	/// Err is returned on failure.
-	fn resolve_headers(self: &mut Self) -> Result<(), String> {
-		let mut request_headers = HeaderMap::new();
+			url.push_str(&query.join("&"));
+			url.push_str(&fragment);
+		}
 
-		// Get a sanitized string that excludes text between quotes.
-		// Also save the extracted text in a vector to later reuse it.
-		let (sanitized, quoted_text) = extract_and_mask_quoted_text(self.predicate.clone());
+		self.url = url;
+		Ok(())
+	}
 
-		// Divide the headers in parts, as each header is always
-		// preceded by `*`.
-		let mut headers_parts = sanitized.split('*');
+	/// Resolve request response JSONPath selector from `self.request`.
+	/// Empty string is used if no selector is found.
+	fn resolve_selector(&mut self) -> Result<(), ParseError> {
+		let mut result_selector = String::from("");
 
-		// The first is always the url and selector
-		headers_parts.next();
+		for operator in &self.request.operators {
+			if let OperatorKind::Selector(value) = &operator.kind {
+				result_selector = self.resolve_value(value)?;
+			}
+		}
 
-		for attribute in headers_parts.into_iter() {
-			// Sanitize the attribute removing any other operator from it
-			let sanitized = attribute.split(['\n', '\t', '^', '~']).nth(0).unwrap();
+		self.result_selector = result_selector;
+		Ok(())
+	}
 
-			// Extract key and value from attribute
-			let (key, mut value) = resolve_key_and_value(sanitized.to_string())?;
+	/// Resolve http request headers from `self.request`.
+	/// Err is returned on failure.
+	fn resolve_headers(&mut self) -> Result<(), ParseError> {
+		let mut request_headers = HeaderMap::new();
 
-			if is_value_a_quoted_reference(value.clone()) {
-				value = quoted_reference_to_value(value, &quoted_text)?;
-			}
+		for operator in &self.request.operators {
+			let (key, value, value_span) = match &operator.kind {
+				OperatorKind::Header { key, value } => {
+					(key, self.resolve_value(value)?, value.span)
+				}
+				_ => continue,
+			};
 
 			// Create header name from lowercase of `key`
 			let header_name = match HeaderName::from_lowercase(key.to_lowercase().as_bytes()) {
-				Err(x) => return Err(x.to_string()),
+				Err(_) => {
+					return Err(self
+						.error(constants::ERR_INVALID_HEADER_NAME, operator.span)
+						.with_help(constants::HELP_HEADER_NAME))
+				}
 				Ok(x) => x,
 			};
 
-			// Create header name from lowercase of `value`, removing opening
-			// and closing quotes if present
+			// Create header value from `value`
 			let header_value = match HeaderValue::from_str(&value[..]) {
-				Err(x) => return Err(x.to_string()),
+				Err(_) => return Err(self.error(constants::ERR_INVALID_HEADER_VALUE, value_span)),
 				Ok(x) => x,
 			};
 
@@ -273,124 +353,357 @@ impl GlueNode {
 
 		// Set `GlueNode` headers map if at least one attribute has been
 		// parsed.
-		if !request_headers.is_empty() {
-			self.headers = Some(request_headers);
-		}
+		self.headers = match request_headers.is_empty() {
+			true => None,
+			false => Some(request_headers),
+		};
 
 		Ok(())
 	}
 
-	/// Resolve http request body `self.predicate`.
+	/// Resolve http request body from `self.request`.
 	/// Err is returned on failure.
-	fn resolve_body(self: &mut Self) -> Result<(), String> {
-		// Predicate is deserialized using command serialization components
-		self.predicate = self.command.deserialize_part(self.predicate.clone());
-
-		match get_raw_json_body(&self.predicate) {
-			// If there is no raw json in the predicate, start looking
-			// at single attributes.
-			None => {
-				let mut request_body: HashMap<String, String> = HashMap::new();
-
-				// Get a sanitized string that excludes text between quotes.
-				// Also save the extracted text in a vector to later reuse it.
-				let (sanitized, quoted_text) = extract_and_mask_quoted_text(self.predicate.clone());
-
-				// Divide the body attributes in parts, as each header is always
-				// preceded by `~`.
-				let mut body_parts = sanitized.split('~');
-
-				// The first is always the url and selector
-				body_parts.next();
-
-				for attribute in body_parts.into_iter() {
-					// Sanitize the attribute removing any other operator from it
-					let sanitized = attribute.split(['\n', '\t', '^', '~']).nth(0).unwrap();
-
-					// Extract key and value from attribute
-					let (key, mut value) = resolve_key_and_value(sanitized.to_string())?;
-
-					// If value is a quoted reference (value temporary removed because
-					// was quoted) - then replace reference with real value
-					if is_value_a_quoted_reference(value.clone()) {
-						value = quoted_reference_to_value(value, &quoted_text)?;
+	fn resolve_body(&mut self) -> Result<(), ParseError> {
+		let mut request_body: HashMap<String, String> = HashMap::new();
+		let mut raw_body: Option<String> = None;
+
+		for operator in &self.request.operators {
+			match &operator.kind {
+				OperatorKind::Body { key, value } => {
+					request_body.insert(key.clone(), self.resolve_value(value)?);
+				}
+				OperatorKind::RawBody(json) => {
+					// Raw json is validated here, to point at the
+					// offending body in case of failure.
+					if let Err(x) = serde_json::from_str::<serde_json::Value>(json) {
+						return Err(self.error(
+							&format!("{}: {}", constants::ERR_INVALID_RAW_BODY, x),
+							operator.span,
+						));
 					}
-
-					// Add key-value pair to body map
-					request_body.insert(key, value);
+					raw_body = Some(json.clone());
 				}
+				_ => (),
+			}
+		}
+
+		self.body = match raw_body {
+			// Raw json takes precedence over single attributes.
+			Some(json) => Some(RequestBody::new(
+				RequestBodyType::ARBITRARY,
+				None,
+				Some(json),
+			)),
+
+			// Set `GlueNode` body map if at least one attribute has been parsed.
+			None if !request_body.is_empty() => Some(RequestBody::new(
+				RequestBodyType::JSON,
+				Some(request_body),
+				None,
+			)),
+
+			None => None,
+		};
+
+		Ok(())
+	}
 
-				// Set `GlueNode` body map if at least one attribute has been parsed.
-				if !request_body.is_empty() {
-					self.body = Some(RequestBody::new(
-						RequestBodyType::JSON,
-						Some(request_body),
-						None,
-					));
+	/// Resolve accepted response statuses from `self.request`.
+	/// Err is returned on invalid statuses.
+	fn resolve_accept(&mut self) -> Result<(), ParseError> {
+		let mut accept = StatusPolicy::default();
+
+		for operator in &self.request.operators {
+			if let OperatorKind::Accept(value) = &operator.kind {
+				let statuses = match StatusPolicy::parse(&self.resolve_value(value)?) {
+					Err(x) => {
+						return Err(self
+							.error(
+								&format!("{} `{}`", constants::ERR_INVALID_STATUS, x),
+								value.span,
+							)
+							.with_help(constants::HELP_ACCEPT))
+					}
+					Ok(x) => x,
+				};
+
+				accept = accept.merge(&statuses);
+			}
+		}
+
+		self.accept = accept;
+		Ok(())
+	}
+
+	/// Resolve request settings from `self.request`.
+	/// Err is returned on unknown settings or invalid values.
+	fn resolve_settings(&mut self) -> Result<(), ParseError> {
+		self.timeout = None;
+		self.retries = None;
+		self.retry_on = None;
+		self.dedupe = None;
+
+		for operator in &self.request.operators {
+			let (key, value) = match &operator.kind {
+				OperatorKind::Setting { key, value } => (key, value),
+				_ => continue,
+			};
+
+			match key.as_str() {
+				constants::SETTING_TIMEOUT => {
+					self.timeout = match parse_duration(&self.resolve_value(value)?) {
+						None => {
+							return Err(self
+								.error(constants::ERR_INVALID_DURATION, value.span)
+								.with_help(constants::HELP_DURATION))
+						}
+						x => x,
+					}
+				}
+				constants::SETTING_RETRIES => {
+					self.retries = match self.resolve_value(value)?.parse::<u32>() {
+						Err(_) => {
+							return Err(self.error(constants::ERR_INVALID_RETRIES, value.span))
+						}
+						Ok(x) => Some(x),
+					}
+				}
+				constants::SETTING_RETRY_ON => {
+					self.retry_on = match StatusPolicy::parse(&self.resolve_value(value)?) {
+						Err(x) => {
+							return Err(self.error(
+								&format!("{} `{}`", constants::ERR_INVALID_STATUS, x),
+								value.span,
+							))
+						}
+						Ok(x) => Some(x),
+					}
+				}
+				constants::SETTING_DEDUPE => {
+					self.dedupe = match self.resolve_value(value)?.parse::<bool>() {
+						Err(_) => {
+							return Err(self
+								.error(constants::ERR_INVALID_BOOLEAN, value.span)
+								.with_help(constants::HELP_BOOLEAN))
+						}
+						Ok(x) => Some(x),
+					}
+				}
+				_ => {
+					return Err(self
+						.error(constants::ERR_UNKNOWN_SETTING, operator.span)
+						.with_help(constants::HELP_SETTING))
 				}
 			}
+		}
 
-			// Append raw json to body in case it has been found.
-			Some(json) => {
-				self.body = Some(RequestBody::new(
-					RequestBodyType::ARBITRARY,
-					None,
-					Some(json),
-				));
+		Ok(())
+	}
+
+	/// Resolve response assertions from `self.request`.
+	/// Err is returned on unknown response fields and invalid
+	/// regular expressions.
+	fn resolve_assertions(&mut self) -> Result<(), ParseError> {
+		let mut assertions = vec![];
+
+		for operator in &self.request.operators {
+			let (subject, comparison, expected) = match &operator.kind {
+				OperatorKind::Assert {
+					subject,
+					comparison,
+					expected,
+				} => (subject, *comparison, expected),
+				_ => continue,
+			};
+
+			if !subject.starts_with(constants::JSONPATH_ROOT) && !Response::is_field(subject) {
+				return Err(self
+					.error(
+						&format!("{} `{}`", constants::ERR_UNKNOWN_RESPONSE_FIELD, subject),
+						operator.span,
+					)
+					.with_help(constants::HELP_ASSERT));
 			}
+
+			let expected = match expected {
+				None => String::new(),
+				Some(x) => self.resolve_value(x)?,
+			};
+
+			let pattern = match comparison {
+				Comparison::Matches => match Regex::new(&expected) {
+					Err(_) => return Err(self.error(constants::ERR_INVALID_REGEX, operator.span)),
+					Ok(x) => Some(x),
+				},
+				_ => None,
+			};
+
+			assertions.push(Assertion {
+				subject: subject.clone(),
+				comparison,
+				expected,
+				pattern,
+			});
 		}
 
+		self.assertions = assertions;
 		Ok(())
 	}
 
-	/// Resolve `self.save_as` starting from predicate, excluding all the
-	/// text between quotes.
-	fn resolve_save_as(self: &mut Self) -> () {
-		self.save_as = match exclude_quoted_text(String::from(&self.predicate))
-			.split('>')
-			.nth(1)
-		{
-			None => None,
-			Some(x) => Some(x.to_string()),
+	/// Apply `defaults` to the resolved request, resolving a relative url
+	/// against the base url and adding default headers the request doesn't
+	/// set itself. Requests reading or assigning variables are left untouched.
+	///
+	/// Err is returned if the url is relative and there is no base url.
+	pub fn apply_defaults(&mut self, defaults: &RequestDefaults) -> Result<(), ParseError> {
+		if self.method == constants::REQ || self.method == constants::LET {
+			return Ok(());
+		}
+
+		self.url = defaults.resolve_url(&self.url);
+
+		if self.url.starts_with(constants::URL_PATH_START) {
+			return Err(self
+				.error(constants::ERR_RELATIVE_URL, self.request.url.span)
+				.with_help(constants::HELP_RELATIVE_URL));
+		}
+
+		if defaults.headers.is_empty() {
+			return Ok(());
+		}
+
+		let mut headers = self.headers.take().unwrap_or_default();
+
+		// Default headers can have more values, all of them are
+		// added unless the request sets or removes the header.
+		let own: Vec<HeaderName> = headers.keys().cloned().collect();
+
+		for (name, value) in &defaults.headers {
+			if !own.contains(name) && !self.request.has_header(name.as_str()) {
+				headers.append(name, value.clone());
+			}
+		}
+
+		self.headers = match headers.is_empty() {
+			true => None,
+			false => Some(headers),
 		};
+
+		Ok(())
+	}
+
+	/// Resolve `self.save_as` from `self.request`.
+	pub fn resolve_save_as(&mut self) {
+		self.save_as = None;
+
+		for operator in &self.request.operators {
+			if let OperatorKind::SaveAs(name) = &operator.kind {
+				self.save_as = Some(name.clone());
+			}
+		}
 	}
 
-	/// Replace all `{}` placeholders from predicate with dependencies
+	/// Resolve `value` to a string.
+	/// Err is returned if it contains a nested request not resolved yet.
+	fn resolve_value(&self, value: &Value) -> Result<String, ParseError> {
+		value
+			.resolve()
+			.ok_or_else(|| self.error(constants::ERR_UNRESOLVED_DEPENDENCY, value.span))
+	}
+
+	/// Create a `ParseError` located at `span` in `self.source`.
+	fn error(&self, message: &str, span: Span) -> ParseError {
+		ParseError::new(message, span, &self.source)
+	}
+
+	/// Replace all dependency placeholders with dependencies
 	/// results taken from a shared memory.
-	pub fn resolve_dependencies(self: &mut Self) -> () {
-		if self.dependencies.len() > 0 {
-			// Dependencies are always in the same order of `{}` placeholders
-			for dependency in &self.dependencies {
-				// Acquire read lock on the dependency mutex
-				let dependency = dependency.lock().unwrap();
-
-				// Replace the next placeholder `{}` in the predicate with the actual
-				// dependency value.
-				self.predicate = self.predicate.replacen("{}", &dependency.result, 1);
+	///
+	/// The placeholder of the dependency nested with `each` is left in place,
+	/// to be replaced with each element of its result with `resolve_each`.
+	pub async fn resolve_dependencies(&mut self) {
+		// Dependencies are always in the same order of `{}` placeholders
+		for (index, dependency) in self.dependencies.iter().enumerate() {
+			// Acquire read lock on the dependency mutex
+			let dependency = dependency.lock().await;
+
+			if self.each_dependency != Some(index) {
+				for value in self.request.values_mut() {
+					value.resolve_dependency(index, &dependency.result);
+				}
+			}
+
+			// Replace the next placeholder `{}` in the predicate with the actual
+			// dependency value.
+			self.predicate = self.predicate.replacen("{}", &dependency.result, 1);
+		}
+	}
+
+	/// Create a copy of the `GlueNode` running for a single `element` of the
+	/// result of the dependency nested with `each`.
+	///
+	/// The copy is not saved as a variable, as the whole list of results
+	/// is saved instead.
+	pub fn resolve_each(&self, element: &str) -> GlueNode {
+		let mut node = self.clone();
+
+		if let Some(index) = node.each_dependency.take() {
+			for value in node.request.values_mut() {
+				value.resolve_dependency(index, element);
 			}
 		}
+
+		node.request
+			.operators
+			.retain(|x| !matches!(x.kind, OperatorKind::SaveAs(_)));
+
+		node
 	}
 
 	/// Print colored `GlueNode` info
-	pub fn print_info(self: &Self) -> () {
+	pub fn print_info(&self) {
 		println!(
 			"> {} {}",
 			self.method.to_uppercase().truecolor(110, 110, 110),
 			self.url.truecolor(110, 110, 110)
 		);
 
-		match &self.body {
-			Some(x) => {
-				for (key, value) in &x.value {
-					println!(
-						"\t{}{}{}",
-						key.truecolor(110, 110, 110),
-						"=".truecolor(110, 110, 110),
-						value.truecolor(110, 110, 110)
-					)
-				}
+		if let Some(x) = &self.body {
+			for (key, value) in &x.value {
+				println!(
+					"\t{}{}{}",
+					key.truecolor(110, 110, 110),
+					"=".truecolor(110, 110, 110),
+					value.truecolor(110, 110, 110)
+				)
 			}
-			_ => (),
 		}
 	}
 }
+
+/// Whether `method` is a valid custom http method.
+/// Custom methods must be written in uppercase to tell them apart
+/// from misspelled keywords.
+fn is_custom_method(method: &str) -> bool {
+	method
+		.chars()
+		.all(|x| x.is_ascii_uppercase() || x == '-' || x == '_')
+		&& Method::from_bytes(method.as_bytes()).is_ok()
+}
+
+/// Percent-encode a query string component. Only unreserved chars
+/// are left as they are.
+fn percent_encode(component: &str) -> String {
+	let mut encoded = String::new();
+
+	for byte in component.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+				encoded.push(byte as char)
+			}
+			_ => encoded.push_str(&format!("%{:02X}", byte)),
+		}
+	}
+
+	encoded
+}
@@ -0,0 +1,875 @@
+use crate::{
+	ast::{
+		Comparison, Directive, DirectiveKind, Operator, OperatorKind, Request, Script, Value,
+		ValuePart,
+	},
+	constants,
+	lexer::{env_parts, is_variable_name, tokenize, Token, TokenKind},
+	ParseError, Span,
+};
+use std::env;
+
+/// Recursive descent parser building `Request` AST nodes out of
+/// gluescript tokens.
+///
+/// Operators are parsed once, depending on the position they are found in:
+/// - urls and attribute values end on whitespace or on the next `^`, `~`
+///   or `*` operator, while `=`, `>`, `?`, `!`, `@` and `%` are literal. This
+///   way urls can keep their own query string and credentials.
+/// - `>`, `?`, `!`, `@` and `%` operators must then be separated by
+///   whitespace when following a url or a value.
+/// - selectors and assertion subjects only end on whitespace, as JSONPath
+///   makes use of operator chars. Assertion subjects also end on `=`.
+/// - text between quotes is always literal, except for nested requests and
+///   environment variables.
+/// - environment variables are replaced by their value while parsing, so
+///   they can be used anywhere a value is.
+pub struct Parser<'a> {
+	/// Source the tokens were read from.
+	source: &'a str,
+
+	/// All tokens of `source`.
+	tokens: Vec<Token>,
+
+	/// Index of the next token to be consumed.
+	position: usize,
+}
+
+impl<'a> Parser<'a> {
+	/// Tokenize `source` and create a `Parser` ready to consume it.
+	/// Err is returned if `source` can't be tokenized.
+	pub fn new(source: &'a str) -> Result<Self, ParseError> {
+		Ok(Parser {
+			source,
+			tokens: tokenize(source)?,
+			position: 0,
+		})
+	}
+
+	/// Parse a whole script: a list of requests separated by `;`.
+	pub fn script(&mut self) -> Result<Script, ParseError> {
+		let mut script = Script::default();
+
+		loop {
+			// Empty statements are allowed and ignored.
+			self.skip(|x| matches!(x, TokenKind::Whitespace | TokenKind::Separator));
+
+			if self.peek().is_none() {
+				break;
+			}
+
+			// Statements starting with `@` are directives, not requests,
+			// and `let` statements can be either.
+			match self.peek().map(|x| &x.kind) {
+				Some(TokenKind::Setting) => script.directives.push(self.directive()?),
+				_ if self.at_let() => self.assignment(&mut script)?,
+				_ => script.requests.push(self.request()?),
+			}
+			self.skip_whitespace();
+
+			// Each request must be followed by a separator or by the end of source.
+			match self.next() {
+				None => break,
+				Some(Token {
+					kind: TokenKind::Separator,
+					..
+				}) => (),
+				Some(token) => {
+					return Err(self.error(
+						constants::ERR_UNEXPECTED_TOKEN,
+						constants::HELP_UNEXPECTED_TOKEN,
+						token.span,
+					))
+				}
+			}
+		}
+
+		Ok(script)
+	}
+
+	/// Parse a directive, like `@base https://example.com`
+	/// or `@header accept=json`.
+	fn directive(&mut self) -> Result<Directive, ParseError> {
+		let start = self.next().unwrap().span.start;
+
+		let (name, name_span) = match self.next() {
+			Some(Token {
+				kind: TokenKind::Word(name),
+				span,
+			}) => (name, span),
+			_ => {
+				return Err(self.error(
+					constants::ERR_UNKNOWN_DIRECTIVE,
+					constants::HELP_DIRECTIVE,
+					Span::new(start, self.previous_end()),
+				))
+			}
+		};
+
+		let kind = match name.as_str() {
+			constants::BASE => {
+				// The base url is used before any request is executed,
+				// so it can't depend on other requests.
+				let has_whitespace = self.skip_whitespace();
+				let url = self.value(false)?;
+
+				match url.resolve() {
+					Some(x) if has_whitespace && !x.is_empty() => DirectiveKind::Base(x),
+					_ => {
+						return Err(self.error(
+							constants::ERR_INVALID_BASE_URL,
+							constants::HELP_BASE_URL,
+							Span::new(start, self.previous_end()),
+						))
+					}
+				}
+			}
+			constants::HEADER => {
+				if !self.skip_whitespace() {
+					return Err(self.error(
+						constants::ERR_UNRESOLVED_ATTR_KEY,
+						constants::HELP_HEADER_DIRECTIVE,
+						Span::new(start, self.previous_end()),
+					));
+				}
+
+				let (key, value) = self.attribute(Span::new(start, name_span.end))?;
+				DirectiveKind::Header { key, value }
+			}
+			_ => {
+				return Err(self.error(
+					constants::ERR_UNKNOWN_DIRECTIVE,
+					constants::HELP_DIRECTIVE,
+					Span::new(start, name_span.end),
+				))
+			}
+		};
+
+		Ok(Directive {
+			kind,
+			span: Span::new(start, self.previous_end()),
+		})
+	}
+
+	/// Check if a `let` statement starts at the next token.
+	fn at_let(&self) -> bool {
+		matches!(
+			(self.peek(), self.tokens.get(self.position + 1)),
+			(
+				Some(Token { kind: TokenKind::Word(x), .. }),
+				Some(Token { kind: TokenKind::Whitespace, .. }),
+			) if x == constants::LET
+		)
+	}
+
+	/// Parse a `let` statement, assigning a variable either a value, like
+	/// `let name = "value"`, or the result of a request, like
+	/// `let id = {get https://example.com^$.id}`.
+	///
+	/// Values depending on requests are added to `script` as a `let` request,
+	/// resolved like a nested request and saved when executed. Other values
+	/// are added as a directive, to be saved before any request.
+	fn assignment(&mut self, script: &mut Script) -> Result<(), ParseError> {
+		let start = self.next().unwrap().span.start;
+		self.skip_whitespace();
+
+		let (name, name_span) = match self.next() {
+			Some(Token {
+				kind: TokenKind::Word(name),
+				span,
+			}) => (name, span),
+			_ => {
+				return Err(self.error(
+					constants::ERR_INVALID_LET,
+					constants::HELP_LET,
+					Span::new(start, self.previous_end()),
+				))
+			}
+		};
+
+		if !is_variable_name(&name) {
+			return Err(self.error(
+				constants::ERR_INVALID_VARIABLE_NAME,
+				constants::HELP_VARIABLE_NAME,
+				name_span,
+			));
+		}
+
+		self.skip_whitespace();
+
+		let has_equals = matches!(
+			self.next(),
+			Some(Token {
+				kind: TokenKind::Equals,
+				..
+			})
+		);
+
+		self.skip_whitespace();
+		let value = self.value(false)?;
+		let span = Span::new(start, self.previous_end());
+
+		if !has_equals || value.is_empty() {
+			return Err(self.error(constants::ERR_INVALID_LET, constants::HELP_LET, span));
+		}
+
+		// The assignment is a request saving its value, so the variable
+		// is only set when the requests before it have been executed.
+		script.requests.push(Request {
+			method: String::from(constants::LET),
+			url: value,
+			operators: vec![Operator {
+				kind: OperatorKind::SaveAs(name),
+				span: name_span,
+			}],
+			span,
+		});
+
+		Ok(())
+	}
+
+	/// Parse a single request: method, url and operators.
+	pub fn request(&mut self) -> Result<Request, ParseError> {
+		self.skip_whitespace();
+
+		// Method must always be the first word of the request.
+		let (method, start) = match self.peek().cloned() {
+			Some(Token {
+				kind: TokenKind::Word(method),
+				span,
+			}) => (method, span.start),
+			_ => {
+				return Err(self.error(
+					constants::ERR_UNRESOLVED_METHOD,
+					constants::HELP_METHOD,
+					self.next_span(),
+				))
+			}
+		};
+		self.position += 1;
+
+		// Url must always follow the method, separated by whitespace.
+		let has_whitespace = self.skip_whitespace();
+		let url = self.value(false)?;
+
+		if !has_whitespace || url.is_empty() {
+			return Err(self.error(
+				constants::ERR_UNRESOLVED_URL,
+				constants::HELP_URL,
+				self.next_span(),
+			));
+		}
+
+		let mut span = Span::new(start, url.span.end);
+		let mut operators = vec![];
+
+		loop {
+			// Operators can be optionally separated by whitespace. If the next
+			// token is not an operator, the whitespace is left to the caller.
+			let checkpoint = self.position;
+			self.skip_whitespace();
+
+			match self.peek().map(|x| &x.kind) {
+				Some(
+					TokenKind::Selector
+					| TokenKind::Body
+					| TokenKind::Header
+					| TokenKind::SaveAs
+					| TokenKind::Query
+					| TokenKind::Accept
+					| TokenKind::Setting
+					| TokenKind::Assert
+					| TokenKind::RawBody(_),
+				) => {
+					let operator = self.operator()?;
+					span = span.to(operator.span);
+					operators.push(operator);
+				}
+				_ => {
+					self.position = checkpoint;
+					break;
+				}
+			}
+		}
+
+		let request = Request {
+			method,
+			url,
+			operators,
+			span,
+		};
+
+		// The request can only run for the elements of a single list.
+		let second_each = request
+			.values()
+			.into_iter()
+			.flat_map(|x| &x.parts)
+			.filter_map(|x| match x {
+				ValuePart::Nested {
+					span, each: true, ..
+				} => Some(*span),
+				_ => None,
+			})
+			.nth(1);
+
+		if let Some(span) = second_each {
+			return Err(self.error(
+				constants::ERR_MULTIPLE_EACH,
+				constants::HELP_MULTIPLE_EACH,
+				span,
+			));
+		}
+
+		Ok(request)
+	}
+
+	/// Parse the operator starting at the next token.
+	fn operator(&mut self) -> Result<Operator, ParseError> {
+		let token = self.next().unwrap();
+
+		let kind = match token.kind {
+			TokenKind::Selector => {
+				let selector = self.value(true)?;
+				if selector.is_empty() {
+					return Err(self.error(
+						constants::ERR_UNRESOLVED_VAL,
+						constants::HELP_SELECTOR,
+						token.span,
+					));
+				}
+				OperatorKind::Selector(selector)
+			}
+
+			TokenKind::Accept => {
+				let statuses = self.value(false)?;
+				if statuses.is_empty() {
+					return Err(self.error(
+						constants::ERR_UNRESOLVED_VAL,
+						constants::HELP_ACCEPT,
+						token.span,
+					));
+				}
+				OperatorKind::Accept(statuses)
+			}
+
+			TokenKind::Body => {
+				let (key, value) = self.attribute(token.span)?;
+				OperatorKind::Body { key, value }
+			}
+
+			TokenKind::Header => match self.removed_header() {
+				Some(key) => OperatorKind::RemoveHeader(key),
+				None => {
+					let (key, value) = self.attribute(token.span)?;
+					OperatorKind::Header { key, value }
+				}
+			},
+
+			TokenKind::Query => {
+				let (key, value) = self.attribute(token.span)?;
+				OperatorKind::Query { key, value }
+			}
+
+			TokenKind::Setting => {
+				let (key, value) = self.attribute(token.span)?;
+				OperatorKind::Setting { key, value }
+			}
+
+			TokenKind::Assert => self.assertion(token.span)?,
+
+			TokenKind::RawBody(json) => {
+				OperatorKind::RawBody(self.interpolate_env(&json, token.span)?)
+			}
+
+			TokenKind::SaveAs => match self.next() {
+				Some(Token {
+					kind: TokenKind::Word(name),
+					..
+				}) => OperatorKind::SaveAs(name),
+				_ => {
+					return Err(self.error(
+						constants::ERR_UNRESOLVED_VAR,
+						constants::HELP_SAVE_AS,
+						token.span,
+					))
+				}
+			},
+
+			_ => {
+				return Err(self.error(
+					constants::ERR_UNEXPECTED_TOKEN,
+					constants::HELP_UNEXPECTED_TOKEN,
+					token.span,
+				))
+			}
+		};
+
+		Ok(Operator {
+			kind,
+			span: Span::new(token.span.start, self.previous_end()),
+		})
+	}
+
+	/// Parse the name of a removed header, written as `-key` right after
+	/// the header operator. `None` is returned if the header is not removed.
+	fn removed_header(&mut self) -> Option<String> {
+		let key = match self.peek().map(|x| &x.kind) {
+			Some(TokenKind::Word(x)) => x.strip_prefix(constants::REMOVE_HEADER)?,
+			_ => return None,
+		};
+
+		// Removed headers have no value.
+		if key.is_empty()
+			|| self
+				.tokens
+				.get(self.position + 1)
+				.is_some_and(|x| x.kind == TokenKind::Equals)
+		{
+			return None;
+		}
+
+		let key = String::from(key);
+		self.position += 1;
+		Some(key)
+	}
+
+	/// Parse an attribute of the form `key=value`, following
+	/// the operator at `operator_span`.
+	fn attribute(&mut self, operator_span: Span) -> Result<(String, Value), ParseError> {
+		let key = match self.next() {
+			Some(Token {
+				kind: TokenKind::Word(key),
+				..
+			}) => key,
+
+			// Keys are always literal, like `?$filter=x`.
+			Some(Token {
+				kind: TokenKind::Variable(_),
+				span,
+			}) => String::from(&self.source[span.start..span.end]),
+			_ => {
+				return Err(self.error(
+					constants::ERR_UNRESOLVED_ATTR_KEY,
+					constants::HELP_ATTRIBUTE,
+					operator_span,
+				))
+			}
+		};
+
+		// Errors on the value point to the whole attribute written so far.
+		let has_equals = matches!(
+			self.next(),
+			Some(Token {
+				kind: TokenKind::Equals,
+				..
+			})
+		);
+		let value = match has_equals {
+			true => self.value(false)?,
+			false => Value::empty(self.previous_end()),
+		};
+
+		if value.is_empty() {
+			return Err(self.error(
+				constants::ERR_UNRESOLVED_ATTR_VAL,
+				constants::HELP_ATTRIBUTE,
+				Span::new(operator_span.start, self.previous_end()),
+			));
+		}
+
+		Ok((key, value))
+	}
+
+	/// Parse an assertion following the operator at `operator_span`.
+	///
+	/// The subject is taken literally up until `=` or whitespace, like a
+	/// selector. A `!` or `~` right before `=` picks the comparison.
+	fn assertion(&mut self, operator_span: Span) -> Result<OperatorKind, ParseError> {
+		let start = self.next_start();
+
+		self.skip(|x| {
+			matches!(
+				x,
+				TokenKind::Word(_)
+					| TokenKind::Selector
+					| TokenKind::Body
+					| TokenKind::Header
+					| TokenKind::SaveAs
+					| TokenKind::Query
+					| TokenKind::Accept
+					| TokenKind::Setting
+					| TokenKind::Assert
+			)
+		});
+
+		let mut subject = &self.source[start..self.previous_end().max(start)];
+
+		let has_equals = matches!(
+			self.peek(),
+			Some(Token {
+				kind: TokenKind::Equals,
+				..
+			})
+		);
+
+		let comparison = match has_equals {
+			false => Comparison::Exists,
+			true => {
+				self.position += 1;
+
+				let comparison = match subject.chars().last() {
+					Some(constants::NOT) => Comparison::NotEquals,
+					Some(constants::MATCHES) => Comparison::Matches,
+					_ => Comparison::Equals,
+				};
+
+				if comparison != Comparison::Equals {
+					subject = &subject[..subject.len() - 1];
+				}
+
+				comparison
+			}
+		};
+
+		let expected = match has_equals {
+			true => Some(self.value(false)?),
+			false => None,
+		};
+
+		if subject.is_empty() || expected.as_ref().is_some_and(|x| x.is_empty()) {
+			return Err(self.error(
+				constants::ERR_UNRESOLVED_VAL,
+				constants::HELP_ASSERT,
+				Span::new(operator_span.start, self.previous_end()),
+			));
+		}
+
+		Ok(OperatorKind::Assert {
+			subject: String::from(subject),
+			comparison,
+			expected,
+		})
+	}
+
+	/// Parse a value made of words, quoted text and nested requests,
+	/// up until the first whitespace.
+	///
+	/// Unless `is_selector` is true, the next operator ends the value too.
+	fn value(&mut self, is_selector: bool) -> Result<Value, ParseError> {
+		let mut value = Value::empty(self.next_start());
+
+		while let Some(token) = self.peek().cloned() {
+			let span = token.span;
+
+			match token.kind {
+				// Words are read from the token, as escapes are dropped.
+				TokenKind::Word(x) => {
+					self.position += 1;
+					value.push_text(&x);
+				}
+
+				TokenKind::Equals
+				| TokenKind::SaveAs
+				| TokenKind::Query
+				| TokenKind::Accept
+				| TokenKind::Setting
+				| TokenKind::Assert => {
+					self.position += 1;
+					value.push_text(&self.source[span.start..span.end]);
+				}
+
+				TokenKind::Selector | TokenKind::Body | TokenKind::Header if is_selector => {
+					self.position += 1;
+					value.push_text(&self.source[span.start..span.end]);
+				}
+
+				TokenKind::Env { name, default } => {
+					self.position += 1;
+					value.push_text(&self.env(&name, default, span)?);
+				}
+
+				TokenKind::Variable(name) => {
+					self.position += 1;
+					value.parts.push(variable(name, span));
+				}
+
+				TokenKind::Quote => self.quoted(&mut value)?,
+
+				TokenKind::OpenDelimiter => self.nested(&mut value)?,
+
+				_ => break,
+			}
+
+			value.span.end = self.previous_end();
+		}
+
+		Ok(value)
+	}
+
+	/// Parse quoted text into `value`, starting from the opening quote.
+	fn quoted(&mut self, value: &mut Value) -> Result<(), ParseError> {
+		let parts = value.parts.len();
+
+		// Opening quote
+		self.position += 1;
+
+		loop {
+			match self.peek().map(|x| x.kind.clone()) {
+				None => {
+					return Err(self.error(
+						constants::ERR_UNTERMINATED_STRING,
+						constants::HELP_UNTERMINATED_STRING,
+						self.next_span(),
+					))
+				}
+				Some(TokenKind::Quote) => {
+					self.position += 1;
+					break;
+				}
+				Some(TokenKind::Text(text)) => {
+					self.position += 1;
+					value.push_text(&text);
+				}
+				Some(TokenKind::Env { name, default }) => {
+					let span = self.next_span();
+					self.position += 1;
+					value.push_text(&self.env(&name, default, span)?);
+				}
+				Some(TokenKind::Variable(name)) => {
+					let span = self.next_span();
+					self.position += 1;
+					value.parts.push(variable(name, span));
+				}
+				Some(TokenKind::OpenDelimiter) => self.nested(value)?,
+				Some(_) => {
+					return Err(self.error(
+						constants::ERR_UNEXPECTED_TOKEN,
+						constants::HELP_UNEXPECTED_TOKEN,
+						self.next_span(),
+					))
+				}
+			}
+		}
+
+		// Empty quotes still produce an (empty) value.
+		if value.parts.len() == parts {
+			value.push_text("");
+		}
+
+		Ok(())
+	}
+
+	/// Parse a nested request into `value`, starting from the open delimiter.
+	/// The request can be preceded by `each`, to run the owner request once
+	/// for each element of its result.
+	fn nested(&mut self, value: &mut Value) -> Result<(), ParseError> {
+		let start = self.next().unwrap().span.start;
+		self.skip_whitespace();
+
+		// `each` is followed by whitespace, as it can't be a method.
+		let each = matches!(
+			(self.peek(), self.tokens.get(self.position + 1)),
+			(
+				Some(Token { kind: TokenKind::Word(x), .. }),
+				Some(Token { kind: TokenKind::Whitespace, .. }),
+			) if x == constants::EACH
+		);
+
+		if each {
+			self.position += 1;
+		}
+
+		let request = self.request()?;
+		self.skip_whitespace();
+
+		match self.next() {
+			Some(Token {
+				kind: TokenKind::CloseDelimiter,
+				span,
+			}) => {
+				value.parts.push(ValuePart::Nested {
+					request: Box::new(request),
+					span: Span::new(start, span.end),
+					each,
+				});
+				Ok(())
+			}
+			None => Err(self.error(
+				constants::ERR_UNCLOSED_DELIMITER,
+				constants::HELP_UNCLOSED_DELIMITER,
+				Span::new(start, start + 1),
+			)),
+			Some(token) => Err(self.error(
+				constants::ERR_UNEXPECTED_TOKEN,
+				constants::HELP_UNEXPECTED_TOKEN,
+				token.span,
+			)),
+		}
+	}
+
+	/// Read the value of the environment variable `name`, written at `span`.
+	///
+	/// `default` is used if the variable is not set or empty, otherwise
+	/// an error is returned if the variable is not set.
+	fn env(&self, name: &str, default: Option<String>, span: Span) -> Result<String, ParseError> {
+		match (env::var(name), default) {
+			(Ok(x), Some(default)) if x.is_empty() => Ok(default),
+			(Ok(x), _) => Ok(x),
+			(Err(_), Some(default)) => Ok(default),
+			(Err(_), None) => Err(self.error(
+				constants::ERR_UNDEFINED_ENV,
+				constants::HELP_UNDEFINED_ENV,
+				span,
+			)),
+		}
+	}
+
+	/// Replace all environment variables in the raw `text` of the token at
+	/// `span` with their values. Errors are located at the whole token.
+	fn interpolate_env(&self, text: &str, span: Span) -> Result<String, ParseError> {
+		let mut interpolated = String::new();
+		let mut rest = text;
+
+		while let Some(start) = rest.find(constants::ENV_START) {
+			let content = &rest[start + constants::ENV_START.len()..];
+
+			let end = match content.find(constants::ENV_END) {
+				None => {
+					return Err(self.error(constants::ERR_UNCLOSED_ENV, constants::HELP_ENV, span))
+				}
+				Some(x) => x,
+			};
+
+			let (name, default) = match env_parts(&content[..end]) {
+				None => {
+					return Err(self.error(
+						constants::ERR_INVALID_ENV_NAME,
+						constants::HELP_ENV,
+						span,
+					))
+				}
+				Some(x) => x,
+			};
+
+			interpolated.push_str(&rest[..start]);
+			interpolated.push_str(&self.env(name, default.map(String::from), span)?);
+			rest = &content[end + constants::ENV_END.len_utf8()..];
+		}
+
+		interpolated.push_str(rest);
+		Ok(interpolated)
+	}
+
+	/// Create a `ParseError` located at `span`, with a `help` hint.
+	fn error(&self, message: &str, help: &str, span: Span) -> ParseError {
+		ParseError::new(message, span, self.source).with_help(help)
+	}
+
+	/// Get the next token without consuming it.
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.position)
+	}
+
+	/// Consume the next token.
+	fn next(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.position).cloned();
+		if token.is_some() {
+			self.position += 1;
+		}
+		token
+	}
+
+	/// Consume tokens as long as `predicate` holds.
+	/// Return true if at least one token was consumed.
+	fn skip(&mut self, predicate: impl Fn(&TokenKind) -> bool) -> bool {
+		let start = self.position;
+		while let Some(token) = self.peek() {
+			if !predicate(&token.kind) {
+				break;
+			}
+			self.position += 1;
+		}
+		self.position > start
+	}
+
+	/// Consume whitespace tokens, if any.
+	fn skip_whitespace(&mut self) -> bool {
+		self.skip(|x| *x == TokenKind::Whitespace)
+	}
+
+	/// Byte offset where the next token starts.
+	fn next_start(&self) -> usize {
+		self.peek().map_or(self.source.len(), |x| x.span.start)
+	}
+
+	/// Span of the next token, or an empty span at the end of source.
+	fn next_span(&self) -> Span {
+		self.peek()
+			.map_or(Span::new(self.source.len(), self.source.len()), |x| x.span)
+	}
+
+	/// Byte offset where the last consumed token ends.
+	fn previous_end(&self) -> usize {
+		match self.position {
+			0 => 0,
+			x => self.tokens[x - 1].span.end,
+		}
+	}
+}
+
+/// Create the nested request reading the saved variable `name`, written
+/// as `$name` at `span`, like `{req name}`.
+fn variable(name: String, span: Span) -> ValuePart {
+	ValuePart::Nested {
+		request: Box::new(Request {
+			method: String::from(constants::REQ),
+			url: Value {
+				parts: vec![ValuePart::Text(name)],
+				span,
+			},
+			operators: vec![],
+			span,
+		}),
+		span,
+		each: false,
+	}
+}
+
+/// Parse a script made of one or more requests and directives
+/// separated by `;`.
+pub fn parse(source: &str) -> Result<Script, ParseError> {
+	Parser::new(source)?.script()
+}
+
+/// Parse a script that must contain exactly one request.
+///
+/// A variable assignment, like `let id = "1"`, is a request too, so it
+/// can be parsed on its own and executed like any other request.
+pub fn parse_request(source: &str) -> Result<Request, ParseError> {
+	let script = parse(source)?;
+
+	// Directives apply to all requests of a script, so a single
+	// request can't have them.
+	if let Some(x) = script.directives.first() {
+		return Err(
+			ParseError::new(constants::ERR_UNEXPECTED_DIRECTIVE, x.span, source)
+				.with_help(constants::HELP_MULTIPLE_REQUESTS),
+		);
+	}
+
+	let mut requests = script.requests;
+
+	match requests.len() {
+		0 => Err(ParseError::new(
+			constants::ERR_UNRESOLVED_METHOD,
+			Span::new(0, source.len()),
+			source,
+		)
+		.with_help(constants::HELP_METHOD)),
+		1 => Ok(requests.remove(0)),
+		_ => Err(
+			ParseError::new(constants::ERR_MULTIPLE_REQUESTS, requests[1].span, source)
+				.with_help(constants::HELP_MULTIPLE_REQUESTS),
+		),
+	}
+}
@@ -0,0 +1,97 @@
+use crate::constants;
+use reqwest::{header::HeaderMap, StatusCode, Version};
+use std::{collections::BTreeMap, time::Duration};
+
+/// Everything received from an executed http request.
+#[derive(Debug, Clone)]
+pub struct Response {
+	/// Response status code.
+	pub status: StatusCode,
+
+	/// Http version the response was received with.
+	pub version: Version,
+
+	/// Response headers.
+	pub headers: HeaderMap,
+
+	/// Response body, as text.
+	pub body: String,
+
+	/// Time elapsed from sending the request to reading the whole body.
+	pub duration: Duration,
+
+	/// Final url of the response, after following redirects.
+	pub url: String,
+}
+
+impl Response {
+	/// Get a response field by its selector name:
+	/// - `status`: status code
+	/// - `url`: final url, after redirects
+	/// - `duration`: elapsed milliseconds
+	/// - `headers`: all headers, as a JSON object
+	/// - `headers.name`: value of the `name` header
+	///
+	/// `None` is returned if no field matches `selector`.
+	pub fn field(&self, selector: &str) -> Option<String> {
+		match selector {
+			constants::FIELD_STATUS => Some(self.status.as_u16().to_string()),
+			constants::FIELD_URL => Some(self.url.clone()),
+			constants::FIELD_DURATION => Some(self.duration.as_millis().to_string()),
+			constants::FIELD_HEADERS => Some(self.headers_json()),
+			x => {
+				let name = x
+					.strip_prefix(constants::FIELD_HEADERS)?
+					.strip_prefix('.')?;
+				let value = self.headers.get(name)?;
+				Some(String::from_utf8_lossy(value.as_bytes()).into())
+			}
+		}
+	}
+
+	/// Whether `selector` is the name of a response field,
+	/// as accepted by `Response::field`.
+	pub fn is_field(selector: &str) -> bool {
+		match selector {
+			constants::FIELD_STATUS
+			| constants::FIELD_URL
+			| constants::FIELD_DURATION
+			| constants::FIELD_HEADERS => true,
+			x => x
+				.strip_prefix(constants::FIELD_HEADERS)
+				.and_then(|x| x.strip_prefix('.'))
+				.is_some_and(|x| !x.is_empty()),
+		}
+	}
+
+	/// Render headers as a pretty JSON object, so they can be
+	/// selected and saved like any other response.
+	pub fn headers_json(&self) -> String {
+		let headers: BTreeMap<&str, String> = self
+			.headers
+			.iter()
+			.map(|(key, value)| {
+				(
+					key.as_str(),
+					String::from_utf8_lossy(value.as_bytes()).into(),
+				)
+			})
+			.collect();
+
+		serde_json::to_string_pretty(&headers).unwrap_or_default()
+	}
+
+	/// Render status line and headers, as they were received.
+	pub fn head(&self) -> String {
+		let mut head = format!("{:?} {}", self.version, self.status);
+
+		for (key, value) in &self.headers {
+			head.push('\n');
+			head.push_str(key.as_str());
+			head.push_str(": ");
+			head.push_str(&String::from_utf8_lossy(value.as_bytes()));
+		}
+
+		head
+	}
+}
@@ -0,0 +1,24 @@
+/// A byte range into the source a token or an AST node was parsed from.
+///
+/// `start` is inclusive, `end` is exclusive, so `&source[span.start..span.end]`
+/// is always the exact text that produced the element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+}
+
+impl Span {
+	/// Create a new `Span` from its `start` and `end` byte offsets.
+	pub fn new(start: usize, end: usize) -> Self {
+		Span { start, end }
+	}
+
+	/// Create the smallest `Span` covering both `self` and `other`.
+	pub fn to(self, other: Span) -> Self {
+		Span {
+			start: self.start.min(other.start),
+			end: self.end.max(other.end),
+		}
+	}
+}
@@ -0,0 +1,63 @@
+use crate::constants;
+use reqwest::StatusCode;
+
+/// Response statuses accepted as a success.
+///
+/// 2xx and 3xx statuses are always accepted, while any other status
+/// must be explicitly accepted, either as a single code like `404`,
+/// or as a whole class like `4xx`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusPolicy {
+	/// Inclusive ranges of accepted statuses.
+	accepted: Vec<(u16, u16)>,
+}
+
+impl StatusPolicy {
+	/// Parse a comma separated list of statuses, like `404,5xx`.
+	/// Err is returned with the first invalid status.
+	pub fn parse(statuses: &str) -> Result<Self, String> {
+		let mut policy = StatusPolicy::default();
+
+		for status in statuses.split(constants::LIST_SEPARATOR) {
+			let status = status.trim();
+
+			let range = match status.to_lowercase().strip_suffix(constants::STATUS_CLASS) {
+				Some(class) => match class.parse::<u16>() {
+					Ok(x) if (1..=5).contains(&x) => (x * 100, x * 100 + 99),
+					_ => return Err(String::from(status)),
+				},
+				None => match status.parse::<u16>() {
+					Ok(x) if (100..=599).contains(&x) => (x, x),
+					_ => return Err(String::from(status)),
+				},
+			};
+
+			policy.accepted.push(range);
+		}
+
+		Ok(policy)
+	}
+
+	/// Whether `status` is accepted as a success.
+	pub fn accepts(&self, status: StatusCode) -> bool {
+		status.is_success() || status.is_redirection() || self.contains(status)
+	}
+
+	/// Whether `status` has been explicitly listed in the policy.
+	pub fn contains(&self, status: StatusCode) -> bool {
+		let code = status.as_u16();
+
+		self.accepted
+			.iter()
+			.any(|(min, max)| *min <= code && code <= *max)
+	}
+
+	/// Create a new policy accepting all statuses accepted by
+	/// `self` or by `other`.
+	pub fn merge(&self, other: &StatusPolicy) -> Self {
+		let mut accepted = self.accepted.clone();
+		accepted.extend(other.accepted.iter().copied());
+
+		StatusPolicy { accepted }
+	}
+}
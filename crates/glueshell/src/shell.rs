@@ -1,5 +1,5 @@
 use colored::*;
-use gluerunner::Stack;
+use gluerunner::{GlueError, Stack};
 use std::io::{stdin, stdout, Write};
 
 /// An interactive shell that runs glue commands using a stack.
@@ -15,15 +15,22 @@ pub struct Shell {
 
 	/// Verbose mode toggle
 	verbose: bool,
+
+	/// Toggle printing of response status line and headers
+	include: bool,
 }
 
 impl Shell {
 	/// Creates a new `Shell` instance with an empty `Stack`.
-	pub fn new(verbose: bool) -> Self {
+	pub fn new(verbose: bool, include: bool) -> Self {
+		let mut stack = Stack::new();
+		stack.set_include(include);
+
 		Shell {
-			stack: Stack::new(),
+			stack,
 			history: vec![],
 			verbose,
+			include,
 		}
 	}
 
@@ -32,7 +39,7 @@ impl Shell {
 	///
 	/// Note that a `None` command equals to a prompt with a `exit` or `quit`
 	/// string: empty string will just be ignored and the loop will continue.
-	pub async fn start(self: &mut Self) -> () {
+	pub async fn start(&mut self) -> () {
 		loop {
 			// Get next command from prompt. Break loop if None.
 			let glue_command = match self.prompt() {
@@ -40,60 +47,68 @@ impl Shell {
 				Some(x) => x,
 			};
 
-			if glue_command != "" {
+			if !glue_command.is_empty() {
 				// Save command to history.
 				self.history.push(glue_command.clone());
 
 				// Add command to stack, print error and skip loop iteration on error.
-				match self.stack.push_runner_from_string(&glue_command, false) {
-					Err(x) => {
-						self.print_err(x);
-						return;
-					}
-					_ => (),
+				if let Err(x) = self.stack.push_runner_from_string(&glue_command, false) {
+					self.print_err(&x);
+					continue;
 				};
 
-				// Execute the command and print result.
+				// Execute the command and print result, if it's not an assignment.
 				match self.stack.execute_next().await {
-					Err(x) => self.print_err(x),
-					Ok(_) => println!("{}", self.stack.current().unwrap().result.as_ref().unwrap()),
+					Err(x) => self.print_err(&x),
+					Ok(_) if self.stack.current().unwrap().assignment => (),
+					Ok(_) => println!("{}", self.stack.current().unwrap().output(self.include)),
 				};
 			}
 		}
 	}
 
 	/// Load content of a file into the execution stack.
-	pub fn load_file(self: &mut Self, path: String) -> Result<(), String> {
+	pub fn load_file(&mut self, path: String) -> Result<(), GlueError> {
 		self.stack.push_from_file(path, self.verbose)?;
 		Ok(())
 	}
 
+	/// Load `script` into the execution stack, like the content of a file.
+	pub fn load_script(&mut self, script: String) -> Result<(), GlueError> {
+		self.stack.push_from_script(script, self.verbose)?;
+		Ok(())
+	}
+
 	/// Execute all runners in the stack consecutively.
-	pub async fn execute_all(self: &mut Self) -> Result<(), String> {
+	pub async fn execute_all(&mut self) -> Result<(), GlueError> {
 		self.stack.execute_all().await?;
 		Ok(())
 	}
 
 	/// Add command to execution stack.
-	pub fn command(self: &mut Self, command: String) -> Result<(), String> {
+	pub fn command(&mut self, command: String) -> Result<(), GlueError> {
 		self.stack.push_runner_from_string(&command, self.verbose)?;
 		Ok(())
 	}
 
 	/// Command prompt from stdin.
-	/// Return `None` if "exit" or "quit" are returned to stdin.
-	fn prompt(self: &Self) -> Option<String> {
+	/// Return `None` if "exit" or "quit" are returned to stdin, or if
+	/// stdin is closed.
+	fn prompt(&self) -> Option<String> {
 		let mut line = String::new();
 
 		print!("{} ", "glue >".green());
 
-		// Flush stdout to remove newline after prompt.
-		stdout().flush().unwrap();
+		// Flush stdout to remove newline after prompt. Failing to do so
+		// only delays the prompt, so the error is ignored.
+		let _ = stdout().flush();
 
-		// Read line from stdin.
-		stdin()
-			.read_line(&mut line)
-			.expect("Error: Could not read a line");
+		// Read line from stdin. The end of input, or a failure reading
+		// it, ends the prompt like "exit".
+		match stdin().read_line(&mut line) {
+			Ok(0) | Err(_) => return None,
+			Ok(_) => (),
+		}
 
 		// Return `None` if "quit" or "exit" provided.
 		// Otherwise return the whole `Some(line)`
@@ -104,7 +119,12 @@ impl Shell {
 	}
 
 	/// Print an error.
-	fn print_err(self: &Self, err: String) -> () {
-		println!("{} {}", "glue >".red(), err.red());
+	/// Parse errors are rendered as diagnostics, already colored and
+	/// printed below the prompt to keep carets aligned.
+	fn print_err(&self, err: &GlueError) {
+		match err {
+			GlueError::Parse(_) => println!("{}\n{}", "glue >".red(), err.diagnostic()),
+			_ => println!("{} {}", "glue >".red(), err.to_string().red()),
+		}
 	}
 }
@@ -1,14 +1,97 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
+	#[clap(subcommand)]
+	pub command: Option<Command>,
+
 	pub request: Option<String>,
 
 	#[clap(short, long, value_parser)]
 	pub file: Option<String>,
 
-	#[clap(short, long)]
+	#[clap(short, long, global = true)]
 	pub verbose: bool,
+
+	/// Print response status line and headers before the result
+	#[clap(short, long)]
+	pub include: bool,
+
+	/// Accept these response statuses as a success, other than 2xx and 3xx,
+	/// like `404,5xx`
+	#[clap(short, long, value_parser, global = true)]
+	pub accept: Option<String>,
+
+	/// Maximum time to connect to a server, like `2s` or `500ms`
+	#[clap(long, value_parser, global = true)]
+	pub connect_timeout: Option<String>,
+
+	/// Maximum time a request can take, like `30s` or `1m`
+	#[clap(short, long, value_parser, global = true)]
+	pub timeout: Option<String>,
+
+	/// Retry failed requests with idempotent methods up to this many times
+	#[clap(short, long, value_parser, global = true)]
+	pub retries: Option<u32>,
+
+	/// Retry responses with these statuses, like `429,5xx`
+	#[clap(long, value_parser, global = true)]
+	pub retry_on: Option<String>,
+
+	/// Maximum number of requests in flight at once
+	#[clap(short, long, value_parser, global = true)]
+	pub concurrency: Option<usize>,
+
+	/// Maximum requests per second to a host, like `api.example.com=5`,
+	/// or to each host, like `5`. Can be repeated
+	#[clap(long, value_parser, global = true)]
+	pub rate_limit: Vec<String>,
+
+	/// What to do when a request fails: `stop` the execution, `continue`
+	/// with the following requests, or `skip` requests using variables
+	/// saved by failed ones
+	#[clap(long, value_parser, global = true)]
+	pub on_error: Option<String>,
+
+	/// Resolve urls starting with `/` against this url, like
+	/// `https://api.example.com`
+	#[clap(short, long, value_parser, global = true)]
+	pub base_url: Option<String>,
+
+	/// Send this header with every request that doesn't set it,
+	/// like `authorization: Bearer xxx`. Can be repeated
+	#[clap(short = 'H', long = "header", value_parser, global = true)]
+	pub headers: Vec<String>,
+
+	/// Send all requests through this proxy, like `http://localhost:8080`
+	#[clap(long, value_parser, global = true)]
+	pub proxy: Option<String>,
+
+	/// Read profiles from this config file, instead of `glue.json`
+	#[clap(long, value_parser, global = true)]
+	pub config: Option<String>,
+
+	/// Use the settings of this profile of the config file
+	#[clap(short, long, value_parser, global = true)]
+	pub profile: Option<String>,
+
+	/// Write a JUnit XML report of the executed requests to this file
+	#[clap(long, value_parser, global = true)]
+	pub junit: Option<String>,
+
+	/// Write a TAP report of the executed requests to this file
+	#[clap(long, value_parser, global = true)]
+	pub tap: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+	/// Run files as tests, reporting the outcome of their assertions
+	Test {
+		/// Files to run
+		#[clap(required = true, value_parser)]
+		files: Vec<String>,
+	},
 }
 
 pub fn command_args() -> Args {
@@ -0,0 +1,283 @@
+use crate::args::Args;
+use gluescript::{constants, RequestDefaults};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+/// Config file read from the working directory, if no other is given.
+pub const DEFAULT_CONFIG: &str = "glue.json";
+
+/// Profile selected when no other is given, if the config file has one.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings of a named profile of the config file, like:
+/// ```json
+/// {
+///   "profiles": {
+///     "staging": {
+///       "base_url": "https://staging.example.com",
+///       "variables": { "token": "abc" },
+///       "headers": { "accept": "application/json" },
+///       "timeout": "30s",
+///       "connect_timeout": "2s",
+///       "proxy": "http://localhost:8080"
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Profile {
+	/// Variables saved in the heap before any request is executed.
+	pub variables: Vec<(String, String)>,
+
+	/// Base url and headers of all requests.
+	pub defaults: RequestDefaults,
+
+	/// Timeout of whole requests, unless given with `--timeout`.
+	pub timeout: Option<String>,
+
+	/// Timeout of connections, unless given with `--connect-timeout`.
+	pub connect_timeout: Option<String>,
+
+	/// Proxy of all requests, unless given with `--proxy`.
+	pub proxy: Option<String>,
+}
+
+impl Profile {
+	/// Fill the settings of `args` not given on the command line
+	/// with the ones of the profile.
+	pub fn fill_args(&self, args: &mut Args) {
+		args.timeout = args.timeout.take().or_else(|| self.timeout.clone());
+		args.connect_timeout = args
+			.connect_timeout
+			.take()
+			.or_else(|| self.connect_timeout.clone());
+		args.proxy = args.proxy.take().or_else(|| self.proxy.clone());
+	}
+}
+
+/// Load the profile selected in `args` from the config file, if any.
+///
+/// The config file is the one given with `--config`, or `glue.json` if it
+/// exists. Without `--profile`, the `default` profile is loaded if present.
+pub fn load(args: &Args) -> Result<Option<Profile>, String> {
+	let path = match &args.config {
+		Some(x) => x.as_str(),
+		None if Path::new(DEFAULT_CONFIG).is_file() => DEFAULT_CONFIG,
+
+		// A profile can't be selected without a config file.
+		None => {
+			return match &args.profile {
+				Some(x) => Err(format!("{} `{}`", constants::ERR_UNKNOWN_PROFILE, x)),
+				None => Ok(None),
+			}
+		}
+	};
+
+	let content = fs::read_to_string(path)
+		.map_err(|x| format!("{} `{}`: {}", constants::ERR_READ_CONFIG, path, x))?;
+
+	parse(&content, args.profile.as_deref())
+		.map_err(|x| format!("{} `{}`: {}", constants::ERR_INVALID_CONFIG, path, x))
+}
+
+/// Parse the profile called `name`, or the default one, from the
+/// content of a config file.
+fn parse(content: &str, name: Option<&str>) -> Result<Option<Profile>, String> {
+	let config: Value = serde_json::from_str(content).map_err(|x| x.to_string())?;
+
+	let profiles = match config.get("profiles") {
+		None => &Value::Null,
+		Some(x) if x.is_object() => x,
+		Some(_) => return Err(String::from("`profiles` must be an object")),
+	};
+
+	let profile = match (name, profiles.get(name.unwrap_or(DEFAULT_PROFILE))) {
+		(_, Some(Value::Object(x))) => x,
+		(_, Some(_)) => return Err(String::from("profiles must be objects")),
+		(Some(x), None) => return Err(format!("{} `{}`", constants::ERR_UNKNOWN_PROFILE, x)),
+		(None, None) => return Ok(None),
+	};
+
+	Ok(Some(Profile {
+		variables: object(profile, "variables")?
+			.iter()
+			.map(|(name, value)| match value {
+				// Other values are saved as JSON, to be read with selectors.
+				Value::String(x) => (name.clone(), x.clone()),
+				x => (name.clone(), x.to_string()),
+			})
+			.collect(),
+		defaults: RequestDefaults {
+			base_url: string(profile, "base_url")?,
+			headers: headers(object(profile, "headers")?)?,
+		},
+		timeout: string(profile, "timeout")?,
+		connect_timeout: string(profile, "connect_timeout")?,
+		proxy: string(profile, "proxy")?,
+	}))
+}
+
+/// Read the optional string called `key` of `profile`.
+fn string(profile: &Map<String, Value>, key: &str) -> Result<Option<String>, String> {
+	match profile.get(key) {
+		None => Ok(None),
+		Some(Value::String(x)) => Ok(Some(x.clone())),
+		Some(_) => Err(format!("`{}` must be a string", key)),
+	}
+}
+
+/// Read the optional object called `key` of `profile`, empty if missing.
+fn object(profile: &Map<String, Value>, key: &str) -> Result<Map<String, Value>, String> {
+	match profile.get(key) {
+		None => Ok(Map::new()),
+		Some(Value::Object(x)) => Ok(x.clone()),
+		Some(_) => Err(format!("`{}` must be an object", key)),
+	}
+}
+
+/// Build the headers of a profile, which must all have string values.
+fn headers(values: Map<String, Value>) -> Result<HeaderMap, String> {
+	let mut headers = HeaderMap::new();
+
+	for (name, value) in values {
+		let name = HeaderName::from_bytes(name.as_bytes())
+			.map_err(|_| format!("{} `{}`", constants::ERR_INVALID_HEADER_NAME, name))?;
+
+		let value = value
+			.as_str()
+			.and_then(|x| HeaderValue::from_str(x).ok())
+			.ok_or_else(|| format!("{} `{}`", constants::ERR_INVALID_HEADER_VALUE, value))?;
+
+		headers.insert(name, value);
+	}
+
+	Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+	use std::{env, process};
+
+	const CONFIG: &str = r#"{
+		"profiles": {
+			"default": { "base_url": "https://example.com" },
+			"staging": {
+				"base_url": "https://staging.example.com",
+				"variables": { "token": "abc", "id": 1 },
+				"headers": { "accept": "application/json" },
+				"timeout": "30s",
+				"connect_timeout": "2s",
+				"proxy": "http://localhost:8080"
+			}
+		}
+	}"#;
+
+	fn args(args: &[&str]) -> Args {
+		Args::parse_from([&["glue"], args].concat())
+	}
+
+	#[test]
+	fn it_parses_the_default_profile() {
+		let profile = parse(CONFIG, None).unwrap().unwrap();
+		assert_eq!(
+			profile.defaults.base_url.as_deref(),
+			Some("https://example.com")
+		);
+		assert!(profile.variables.is_empty());
+		assert_eq!(profile.timeout, None);
+
+		assert!(parse(r#"{ "profiles": {} }"#, None).unwrap().is_none());
+		assert!(parse("{}", None).unwrap().is_none());
+	}
+
+	#[test]
+	fn it_parses_profiles_by_name() {
+		let profile = parse(CONFIG, Some("staging")).unwrap().unwrap();
+		assert_eq!(
+			profile.defaults.base_url.as_deref(),
+			Some("https://staging.example.com")
+		);
+		assert_eq!(profile.defaults.headers["accept"], "application/json");
+		assert_eq!(profile.timeout.as_deref(), Some("30s"));
+		assert_eq!(profile.connect_timeout.as_deref(), Some("2s"));
+		assert_eq!(profile.proxy.as_deref(), Some("http://localhost:8080"));
+
+		// Variables other than strings are kept as JSON.
+		let mut variables = profile.variables;
+		variables.sort();
+		assert_eq!(
+			variables,
+			vec![
+				(String::from("id"), String::from("1")),
+				(String::from("token"), String::from("abc")),
+			]
+		);
+	}
+
+	#[test]
+	fn it_rejects_unknown_profiles() {
+		let error = parse(CONFIG, Some("production")).unwrap_err();
+		assert_eq!(
+			error,
+			format!("{} `production`", constants::ERR_UNKNOWN_PROFILE)
+		);
+
+		// Profiles can't be selected without a config file.
+		let error = load(&args(&["--profile", "staging"])).unwrap_err();
+		assert!(error.starts_with(constants::ERR_UNKNOWN_PROFILE));
+	}
+
+	#[test]
+	fn it_rejects_wrong_typed_fields() {
+		let profile = |x: &str| {
+			parse(
+				&format!(r#"{{ "profiles": {{ "default": {} }} }}"#, x),
+				None,
+			)
+		};
+
+		assert!(parse(r#"{ "profiles": [] }"#, None).is_err());
+		assert!(parse(r#"{ "profiles": { "default": 1 } }"#, None).is_err());
+		assert!(profile(r#"{ "base_url": 1 }"#).is_err());
+		assert!(profile(r#"{ "timeout": 30 }"#).is_err());
+		assert!(profile(r#"{ "variables": [] }"#).is_err());
+		assert!(profile(r#"{ "headers": { "accept": 1 } }"#).is_err());
+		assert!(profile(r#"{ "headers": { "bad name": "x" } }"#).is_err());
+		assert!(parse("not json", None).is_err());
+	}
+
+	#[test]
+	fn it_loads_the_config_file() {
+		let path = env::temp_dir().join(format!("glue-config-{}.json", process::id()));
+		fs::write(&path, CONFIG).unwrap();
+		let path = path.to_str().unwrap();
+
+		let profile = load(&args(&["--config", path, "--profile", "staging"]))
+			.unwrap()
+			.unwrap();
+		assert_eq!(profile.timeout.as_deref(), Some("30s"));
+
+		let error = load(&args(&["--config", path, "--profile", "production"])).unwrap_err();
+		assert!(error.starts_with(constants::ERR_INVALID_CONFIG));
+
+		fs::remove_file(path).unwrap();
+
+		let error = load(&args(&["--config", path])).unwrap_err();
+		assert!(error.starts_with(constants::ERR_READ_CONFIG));
+	}
+
+	#[test]
+	fn it_lets_command_line_flags_override_the_profile() {
+		let profile = parse(CONFIG, Some("staging")).unwrap().unwrap();
+
+		let mut args = args(&["--timeout", "5s"]);
+		profile.fill_args(&mut args);
+
+		assert_eq!(args.timeout.as_deref(), Some("5s"));
+		assert_eq!(args.connect_timeout.as_deref(), Some("2s"));
+		assert_eq!(args.proxy.as_deref(), Some("http://localhost:8080"));
+	}
+}
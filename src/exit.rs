@@ -0,0 +1,94 @@
+use gluerunner::GlueError;
+
+/// Exit code of the binary when every request succeeded.
+pub const SUCCESS: i32 = 0;
+
+/// Exit code of failures not covered by other codes, like files
+/// that can't be read or written.
+pub const FAILURE: i32 = 1;
+
+/// Exit code of invalid command line arguments, the same used by `clap`.
+pub const USAGE: i32 = 2;
+
+/// Exit code of scripts that can't be parsed or resolved.
+pub const PARSE: i32 = 3;
+
+/// Exit code of requests that could not be sent or did not complete in time.
+pub const NETWORK: i32 = 4;
+
+/// Exit code of responses with a status not considered a success.
+pub const STATUS: i32 = 5;
+
+/// Exit code of failed assertions.
+pub const ASSERTION: i32 = 6;
+
+/// Exit code of the binary when it fails with `error`.
+pub fn code(error: &GlueError) -> i32 {
+	match error {
+		GlueError::Parse(_) => PARSE,
+		GlueError::Http(_) | GlueError::Timeout(_) => NETWORK,
+		GlueError::Status { .. } => STATUS,
+		GlueError::Assertion(_) => ASSERTION,
+
+		// Skipped requests are caused by another failure, so the
+		// code of the first actual failure is used.
+		GlueError::Failures(x) => x
+			.iter()
+			.find(|x| !matches!(x, GlueError::Skipped { .. }))
+			.map_or(FAILURE, code),
+		_ => FAILURE,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use gluescript::parse_request;
+	use reqwest::StatusCode;
+	use std::io;
+
+	fn parse_error() -> GlueError {
+		GlueError::Parse(parse_request("get").unwrap_err())
+	}
+
+	fn status_error() -> GlueError {
+		GlueError::Status {
+			url: String::from("http://example.com"),
+			status: StatusCode::INTERNAL_SERVER_ERROR,
+			excerpt: String::new(),
+		}
+	}
+
+	fn skipped_error() -> GlueError {
+		GlueError::Skipped {
+			command: String::from("req token"),
+			variable: String::from("token"),
+		}
+	}
+
+	#[test]
+	fn it_maps_errors_to_codes() {
+		let http = reqwest::Client::new().get("not a url").build().unwrap_err();
+
+		assert_eq!(code(&parse_error()), PARSE);
+		assert_eq!(code(&GlueError::Http(http)), NETWORK);
+		assert_eq!(code(&GlueError::Timeout(String::from("get x"))), NETWORK);
+		assert_eq!(code(&status_error()), STATUS);
+		assert_eq!(code(&GlueError::Assertion(vec![])), ASSERTION);
+		assert_eq!(
+			code(&GlueError::UnresolvedVariable(String::from("x"))),
+			FAILURE
+		);
+		assert_eq!(code(&GlueError::Selector(String::from("$.id"))), FAILURE);
+		assert_eq!(code(&GlueError::Io(io::Error::other("x"))), FAILURE);
+		assert_eq!(code(&skipped_error()), FAILURE);
+	}
+
+	#[test]
+	fn it_maps_failures_to_the_first_actual_one() {
+		let failures = vec![skipped_error(), status_error(), parse_error()];
+		assert_eq!(code(&GlueError::Failures(failures)), STATUS);
+		assert_eq!(code(&GlueError::Failures(vec![skipped_error()])), FAILURE);
+		assert_eq!(code(&GlueError::Failures(vec![])), FAILURE);
+	}
+}
@@ -1,38 +1,346 @@
 mod args;
+mod config;
+mod exit;
+mod report;
 
-use args::{command_args, Args};
+use args::{command_args, Args, Command};
+use config::Profile;
+use gluerunner::{
+	ErrorPolicy, GlueError, JUnitReporter, RetryPolicy, SharedReporter, Stack, TapReporter,
+	TestResult, Throttle,
+};
+use gluescript::{constants, parse_duration, StatusPolicy};
 use glueshell::Shell;
+use report::Summary;
+use reqwest::{
+	header::{HeaderName, HeaderValue},
+	Client, Proxy,
+};
+use std::{
+	fs::File,
+	io::{stdin, BufWriter, IsTerminal, Read, Write},
+	path::Path,
+	process,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+/// Name used in place of a file to read the script from stdin.
+const STDIN: &str = "-";
 
 #[tokio::main]
 async fn main() {
-	let args: Args = command_args();
+	let mut args: Args = command_args();
+	let reporters = reporters(&args);
+
+	// Settings of the selected profile are used where not given
+	// on the command line.
+	let profile = match config::load(&args) {
+		Err(x) => {
+			eprintln!("{}", x);
+			process::exit(exit::USAGE);
+		}
+		Ok(x) => x.unwrap_or_default(),
+	};
+
+	profile.fill_args(&mut args);
+
+	// Test files are run on their own, without the shell.
+	if let Some(Command::Test { files }) = &args.command {
+		let summary = run_tests(files, &args, &profile, &reporters).await;
+		finish_reports(&reporters);
+		process::exit(summary.exit_code);
+	}
 
 	// Fresh instance on `glueshell` instantiated to be ready
 	// to run request, file or start interactive mode.
-	let mut shell = Shell::new(args.verbose);
+	let mut shell = Shell::new(args.verbose, args.include);
+	configure(&mut shell.stack, &args, &profile, &reporters);
+
+	let loaded = match (args.file, args.request) {
+		// Read the script from stdin if asked to with `-`.
+		(Some(x), _) | (None, Some(x)) if x == STDIN => shell.load_script(read_stdin()),
+
+		// Use a file content
+		(Some(x), _) => shell.load_file(x),
+
+		// Paths of files are accepted in place of a request too, so
+		// scripts can be executed with a `#!/usr/bin/env glue` shebang.
+		(None, Some(x)) if Path::new(&x).is_file() => shell.load_file(x),
+
+		// Or create `Runner` from command string
+		(None, Some(x)) => shell.command(x),
+
+		// Without file or request, a script piped to stdin is executed.
+		(None, None) if !stdin().is_terminal() => shell.load_script(read_stdin()),
 
-	if args.file.is_none() && args.request.is_none() {
-		// Start the shell in interactive and block till finished
-		// if no file or request are provided.
-		shell.start().await;
-		return;
+		// Otherwise start the shell in interactive and block till finished.
+		(None, None) => {
+			shell.start().await;
+			return;
+		}
+	};
+
+	// Print parse errors along with the offending source line.
+	if let Err(x) = loaded {
+		eprintln!("{}", x.diagnostic());
+		process::exit(exit::code(&x));
 	}
 
-	match args.file {
-		// Create `Runner` from command string if no file is provided
-		None => shell
-			.command(args.request.unwrap())
-			.expect("Error encountered while running command."),
+	// Execute command and print result. Reports are written
+	// even if the execution failed.
+	let executed = shell.execute_all().await;
+	finish_reports(&reporters);
 
-		// Or use a file content
-		Some(x) => shell
-			.load_file(x)
-			.expect("Error encountered while loading file content."),
+	if let Err(x) = executed {
+		eprintln!("{}", x.diagnostic());
+		process::exit(exit::code(&x));
 	};
+}
+
+/// Configure `stack` with the settings of all requests from `args`
+/// and `profile`, exiting on invalid ones, and record its runners
+/// in `reporters`.
+fn configure(stack: &mut Stack, args: &Args, profile: &Profile, reporters: &[SharedReporter]) {
+	for x in reporters {
+		stack.add_reporter(Arc::clone(x));
+	}
+
+	// Variables of the profile can be read by all requests.
+	for (name, value) in &profile.variables {
+		stack.set_variable(name, value);
+	}
+
+	// The base url and headers given on the command line take
+	// precedence over the ones of the profile.
+	let mut defaults = profile.defaults.clone();
+
+	if let Some(x) = &args.base_url {
+		defaults.base_url = Some(x.clone());
+	}
+
+	for x in &args.headers {
+		let (name, value) = header_arg(x);
+		defaults.headers.insert(name, value);
+	}
+
+	stack.set_defaults(defaults);
+
+	// Statuses accepted for every request, other than 2xx and 3xx.
+	if let Some(x) = &args.accept {
+		match StatusPolicy::parse(x) {
+			Err(x) => {
+				eprintln!("{} `{}`", constants::ERR_INVALID_STATUS, x);
+				process::exit(exit::USAGE);
+			}
+			Ok(x) => stack.set_accept(x),
+		}
+	}
+
+	// Retries of requests with idempotent methods, on default statuses
+	// unless otherwise specified.
+	let mut retry = RetryPolicy::new(args.retries.unwrap_or(0));
+
+	if let Some(x) = &args.retry_on {
+		match StatusPolicy::parse(x) {
+			Err(x) => {
+				eprintln!("{} `{}`", constants::ERR_INVALID_STATUS, x);
+				process::exit(exit::USAGE);
+			}
+			Ok(x) => retry.statuses = x,
+		}
+	}
+
+	stack.set_retry(retry);
+
+	// Following requests can be executed even if one fails.
+	if let Some(x) = &args.on_error {
+		match ErrorPolicy::parse(x) {
+			None => {
+				eprintln!("{} `{}`", constants::ERR_INVALID_ERROR_POLICY, x);
+				process::exit(exit::USAGE);
+			}
+			Some(x) => stack.set_error_policy(x),
+		}
+	}
+
+	// Limits shared by all requests, in flight and per host.
+	let mut throttle = Throttle::default();
+
+	if let Some(x) = args.concurrency {
+		if x == 0 {
+			eprintln!("{} `{}`", constants::ERR_INVALID_CONCURRENCY, x);
+			process::exit(exit::USAGE);
+		}
+
+		throttle.set_concurrency(x);
+	}
+
+	for x in &args.rate_limit {
+		match Throttle::parse_rate(x) {
+			None => {
+				eprintln!("{} `{}`", constants::ERR_INVALID_RATE_LIMIT, x);
+				process::exit(exit::USAGE);
+			}
+			Some((host, rate)) => throttle.set_rate(&host, rate),
+		}
+	}
+
+	stack.set_throttle(throttle);
+
+	// Timeouts are set once on the client shared by all requests.
+	let mut client = Client::builder();
+
+	if let Some(x) = &args.connect_timeout {
+		client = client.connect_timeout(duration_arg(x));
+	}
 
-	// Execute command and print result. Panic on error.
-	match shell.execute_all().await {
-		Err(x) => panic!("Error encountered while executing requests: {}", x),
-		_ => (),
+	if let Some(x) = &args.timeout {
+		client = client.timeout(duration_arg(x));
+	}
+
+	if let Some(x) = &args.proxy {
+		match Proxy::all(x) {
+			Err(_) => {
+				eprintln!("{} `{}`", constants::ERR_INVALID_PROXY, x);
+				process::exit(exit::USAGE);
+			}
+			Ok(x) => client = client.proxy(x),
+		}
+	}
+
+	match client.build() {
+		Err(x) => {
+			eprintln!("{}", x);
+			process::exit(exit::FAILURE);
+		}
+		Ok(x) => stack.set_client(x),
+	}
+}
+
+/// Run each of `files` as tests with a `Stack` of its own, printing
+/// the outcome of all checks.
+async fn run_tests(
+	files: &[String],
+	args: &Args,
+	profile: &Profile,
+	reporters: &[SharedReporter],
+) -> Summary {
+	let mut summary = Summary::default();
+
+	for file in files {
+		let mut stack = Stack::new();
+		configure(&mut stack, args, profile, reporters);
+
+		let results = match stack.push_from_file(file.clone(), args.verbose) {
+			Err(x) => Err(record_failed_file(file, x, reporters)),
+			Ok(_) => Ok(stack.execute_tests().await),
+		};
+
+		report::print_file(file, &results, &mut summary);
+	}
+
+	summary.print();
+	summary
+}
+
+/// Record `file`, which could not be read or parsed, as a single failed
+/// test in all `reporters`, so the failure is part of their reports.
+///
+/// `error` is given back, to be printed along with the other results.
+fn record_failed_file(file: &str, error: GlueError, reporters: &[SharedReporter]) -> GlueError {
+	let result = TestResult {
+		request: String::from(file),
+		assertions: vec![],
+		duration: Duration::ZERO,
+		status: None,
+		error: Some(error),
 	};
+
+	for x in reporters {
+		x.lock().unwrap().record(file, &result);
+	}
+
+	result.error.unwrap()
+}
+
+/// Create the reporters requested in `args`, exiting if their
+/// files can't be created.
+fn reporters(args: &Args) -> Vec<SharedReporter> {
+	let mut reporters: Vec<SharedReporter> = vec![];
+
+	if let Some(x) = &args.junit {
+		let reporter = JUnitReporter::new(report_file(x));
+		reporters.push(Arc::new(Mutex::new(reporter)));
+	}
+
+	if let Some(x) = &args.tap {
+		let reporter = TapReporter::new(report_file(x));
+		reporters.push(Arc::new(Mutex::new(reporter)));
+	}
+
+	reporters
+}
+
+/// Create the file at `path` to write a report in, exiting on failure.
+fn report_file(path: &str) -> Box<dyn Write + Send> {
+	match File::create(path) {
+		Err(x) => {
+			eprintln!("{} `{}`: {}", constants::ERR_REPORT_FILE, path, x);
+			process::exit(exit::FAILURE);
+		}
+		Ok(x) => Box::new(BufWriter::new(x)),
+	}
+}
+
+/// Write the reports of all `reporters`, exiting on failure.
+fn finish_reports(reporters: &[SharedReporter]) {
+	for x in reporters {
+		if let Err(x) = x.lock().unwrap().finish() {
+			eprintln!("{}: {}", constants::ERR_REPORT_FILE, x);
+			process::exit(exit::FAILURE);
+		}
+	}
+}
+
+/// Read the whole script from stdin, exiting on failure.
+fn read_stdin() -> String {
+	let mut script = String::new();
+
+	if let Err(x) = stdin().read_to_string(&mut script) {
+		eprintln!("{}: {}", constants::ERR_READ_STDIN, x);
+		process::exit(exit::FAILURE);
+	}
+
+	script
+}
+
+/// Parse a duration from a CLI argument, exiting on failure.
+fn duration_arg(value: &str) -> Duration {
+	match parse_duration(value) {
+		None => {
+			eprintln!("{} `{}`", constants::ERR_INVALID_DURATION, value);
+			process::exit(exit::USAGE);
+		}
+		Some(x) => x,
+	}
+}
+
+/// Parse a header from a CLI argument written as `name: value`,
+/// exiting on failure.
+fn header_arg(header: &str) -> (HeaderName, HeaderValue) {
+	let parsed = header.split_once(':').and_then(|(name, value)| {
+		Some((
+			HeaderName::from_bytes(name.trim().as_bytes()).ok()?,
+			HeaderValue::from_str(value.trim()).ok()?,
+		))
+	});
+
+	match parsed {
+		None => {
+			eprintln!("{} `{}`", constants::ERR_INVALID_HEADER, header);
+			process::exit(exit::USAGE);
+		}
+		Some(x) => x,
+	}
 }
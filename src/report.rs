@@ -0,0 +1,115 @@
+use crate::exit;
+use colored::*;
+use gluerunner::{one_line, GlueError, TestResult};
+
+/// Number of passed and failed checks of a test run.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Summary {
+	pub passed: usize,
+	pub failed: usize,
+
+	/// Exit code of the first failure, `exit::SUCCESS` if none.
+	pub exit_code: i32,
+}
+
+impl Summary {
+	/// Keep the exit code of `error`, if it's the first failure.
+	fn fail_with(&mut self, error: &GlueError) {
+		if self.exit_code == exit::SUCCESS {
+			self.exit_code = exit::code(error);
+		}
+	}
+
+	/// Print the number of passed and failed checks.
+	pub fn print(&self) {
+		let failed = format!("{} failed", self.failed);
+
+		println!(
+			"\n{}, {}",
+			format!("{} passed", self.passed).green(),
+			match self.failed {
+				0 => failed.normal(),
+				_ => failed.red(),
+			}
+		);
+	}
+}
+
+/// Print the outcome of the tests of `file`, counting its checks into `summary`.
+///
+/// Every assertion is a check. Requests without assertions are a check
+/// themselves, passing if they complete. Errors other than failed
+/// assertions are failed checks too.
+pub fn print_file(file: &str, results: &Result<Vec<TestResult>, GlueError>, summary: &mut Summary) {
+	println!("{}", file.bold());
+
+	let results = match results {
+		Err(x) => {
+			summary.fail_with(x);
+			print_check(2, false, &x.diagnostic(), None, summary);
+			return;
+		}
+		Ok(x) => x,
+	};
+
+	for result in results {
+		let request = one_line(&result.request);
+
+		if let Some(x) = &result.error {
+			summary.fail_with(x);
+		}
+
+		// Requests with assertions are a heading for their checks.
+		if !result.assertions.is_empty() {
+			println!("  {} {}", mark(result.passed()), request);
+		}
+
+		for assertion in &result.assertions {
+			let name = assertion.assertion.to_string();
+			print_check(
+				4,
+				assertion.passed(),
+				&name,
+				assertion.failure.as_deref(),
+				summary,
+			);
+		}
+
+		match &result.error {
+			None if result.assertions.is_empty() => print_check(2, true, &request, None, summary),
+			Some(GlueError::Assertion(_)) | None => (),
+			Some(x) => print_check(2, false, &request, Some(&x.to_string()), summary),
+		}
+	}
+}
+
+/// Print a single check called `name` indented by `indent` spaces,
+/// along with why it failed, if it did.
+fn print_check(
+	indent: usize,
+	passed: bool,
+	name: &str,
+	failure: Option<&str>,
+	summary: &mut Summary,
+) {
+	match passed {
+		true => summary.passed += 1,
+		false => summary.failed += 1,
+	}
+
+	println!("{:indent$}{} {}", "", mark(passed), name, indent = indent);
+
+	if let Some(x) = failure {
+		for line in x.lines() {
+			println!("{:indent$}{}", "", line.red(), indent = indent + 4);
+		}
+	}
+}
+
+/// Green tick for passed checks, red cross for failed ones.
+fn mark(passed: bool) -> ColoredString {
+	match passed {
+		true => "✓".green(),
+		false => "✗".red(),
+	}
+}
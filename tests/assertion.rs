@@ -0,0 +1,65 @@
+mod common;
+
+use gluerunner::check_assertions;
+use gluescript::{GlueNode, Response};
+use reqwest::{header::ETAG, StatusCode};
+
+fn get_response() -> Response {
+	common::get_response(
+		StatusCode::OK,
+		&[(ETAG, "abc")],
+		r#"{ "id": 42, "name": "Bob", "tags": ["a", "b"] }"#,
+	)
+}
+
+/// Check the assertions of `command` on the test response, returning
+/// whether each one passed.
+fn check(command: &str) -> Vec<bool> {
+	let mut node = GlueNode::from_string(command).unwrap();
+	node.resolve_predicate().unwrap();
+
+	check_assertions(&node, Some(&get_response()), "")
+		.iter()
+		.map(|x| x.passed())
+		.collect()
+}
+
+#[test]
+fn it_checks_equals() {
+	assert_eq!(
+		check("get http://example.com %status=200 %$.id=42 %$.name=Bob %$.name=Alice"),
+		vec![true, true, true, false]
+	);
+}
+
+#[test]
+fn it_checks_not_equals() {
+	assert_eq!(
+		check("get http://example.com %status!=500 %$.name!=Alice %$.id!=42"),
+		vec![true, true, false]
+	);
+}
+
+#[test]
+fn it_checks_matches() {
+	assert_eq!(
+		check(r#"get http://example.com %$.name~="^B" %$.id~="^[0-9]+$" %headers.etag~="^x""#),
+		vec![true, true, false]
+	);
+}
+
+#[test]
+fn it_checks_exists() {
+	assert_eq!(
+		check("get http://example.com %headers.etag %$.tags %$.missing %headers.location"),
+		vec![true, true, false, false]
+	);
+}
+
+#[test]
+fn it_checks_head_responses_on_headers() {
+	let mut node = GlueNode::from_string("head http://example.com %$.etag=abc").unwrap();
+	node.resolve_predicate().unwrap();
+
+	assert!(check_assertions(&node, Some(&get_response()), "")[0].passed());
+}
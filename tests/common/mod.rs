@@ -0,0 +1,152 @@
+// Each test file uses only some of the helpers.
+#![allow(dead_code)]
+
+use gluescript::Response;
+use reqwest::{
+	header::{HeaderMap, HeaderName, HeaderValue},
+	StatusCode, Version,
+};
+use std::{
+	io::{BufRead, BufReader, Read, Write},
+	net::{TcpListener, TcpStream},
+	sync::{Arc, Mutex},
+	thread,
+	time::Duration,
+};
+
+/// Build a response of `http://example.com` with `status`, `headers`
+/// and `body`, received instantly.
+pub fn get_response(
+	status: StatusCode,
+	headers: &[(HeaderName, &'static str)],
+	body: &str,
+) -> Response {
+	let mut map = HeaderMap::new();
+	for (name, value) in headers {
+		map.insert(name, HeaderValue::from_static(value));
+	}
+
+	Response {
+		status,
+		version: Version::HTTP_11,
+		headers: map,
+		body: String::from(body),
+		duration: Duration::ZERO,
+		url: String::from("http://example.com"),
+	}
+}
+
+/// A local http server recording every request it receives, as
+/// `METHOD /path`, and answering by path:
+///
+/// - `/list/<n>`: a JSON array of the numbers from 1 to `n`.
+/// - `/items/<id>`: a JSON object with the `id` and a `name`.
+/// - `/login`: a JSON object with a `token`.
+/// - `/malformed`: something that is not HTTP.
+/// - `/delay/<ms>`: a JSON object with the `delay`, after `ms` milliseconds.
+/// - anything else: a JSON object echoing the method, path,
+///   lowercase headers and body of the request.
+pub struct Server {
+	/// Url of the server, without a trailing `/`.
+	pub url: String,
+
+	/// Requests received so far, in order.
+	requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl Server {
+	/// Start a new server on a random local port.
+	pub fn start() -> Self {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(Mutex::new(vec![]));
+
+		let recorded = Arc::clone(&requests);
+		thread::spawn(move || {
+			for stream in listener.incoming().flatten() {
+				let recorded = Arc::clone(&recorded);
+				thread::spawn(move || respond(stream, &recorded));
+			}
+		});
+
+		Server { url, requests }
+	}
+
+	/// Number of received requests equal to `request`, like `GET /login`.
+	pub fn count(&self, request: &str) -> usize {
+		self.requests
+			.lock()
+			.unwrap()
+			.iter()
+			.filter(|x| *x == request)
+			.count()
+	}
+}
+
+/// Read a single request from `stream` and answer it, closing the connection.
+fn respond(mut stream: TcpStream, recorded: &Mutex<Vec<String>>) {
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+	let mut line = String::new();
+	reader.read_line(&mut line).unwrap();
+	let mut parts = line.split_whitespace();
+	let method = parts.next().unwrap_or_default().to_string();
+	let path = parts.next().unwrap_or_default().to_string();
+
+	let mut headers = serde_json::Map::new();
+	let mut length = 0;
+
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).unwrap();
+
+		let (name, value) = match line.trim_end().split_once(':') {
+			None => break,
+			Some((name, value)) => (name.to_lowercase(), value.trim().to_string()),
+		};
+
+		if name == "content-length" {
+			length = value.parse().unwrap();
+		}
+
+		headers.insert(name, serde_json::Value::String(value));
+	}
+
+	let mut body = vec![0; length];
+	reader.read_exact(&mut body).unwrap();
+
+	recorded
+		.lock()
+		.unwrap()
+		.push(format!("{} {}", method, path));
+
+	if path == "/malformed" {
+		let _ = write!(stream, "not http\r\n\r\n");
+		return;
+	}
+
+	let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+	let response = match segments.as_slice() {
+		["list", n] => serde_json::json!((1..=n.parse::<u32>().unwrap()).collect::<Vec<_>>()),
+		["items", id] => serde_json::json!({ "id": id, "name": format!("item {}", id) }),
+		["login"] => serde_json::json!({ "token": "t0k" }),
+		["delay", ms] => {
+			thread::sleep(Duration::from_millis(ms.parse().unwrap()));
+			serde_json::json!({ "delay": ms })
+		}
+		_ => serde_json::json!({
+			"method": method,
+			"path": path,
+			"headers": headers,
+			"body": String::from_utf8_lossy(&body),
+		}),
+	}
+	.to_string();
+
+	let _ = write!(
+		stream,
+		"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+		response.len(),
+		response
+	);
+}
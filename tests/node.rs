@@ -1,4 +1,12 @@
-use gluescript::node::GlueNode;
+// Older tests look up keys with owned strings, which newer lints flag.
+#![allow(
+	clippy::unnecessary_to_owned,
+	clippy::needless_borrows_for_generic_args
+)]
+
+use gluescript::{node::GlueNode, RequestDefaults};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
+use std::time::Duration;
 
 const SIMPLE_COMMAND: &str = "get http://example.com";
 const SIMPLE_COMMAND_WITH_BODY: &str =
@@ -31,12 +39,12 @@ fn it_resolves_url_correctly() {
 	assert_eq!("http://example.com".to_string(), node.url);
 }
 
-#[test]
-fn it_resolves_dependency_correctly() {
+#[tokio::test]
+async fn it_resolves_dependency_correctly() {
 	let mut node = get_node(WITH_SELECTOR_NESTED_COMMAND.to_string());
 	assert_eq!(node.dependencies.len(), 1);
 
-	let mut w_dep = node.dependencies[0].lock().unwrap();
+	let mut w_dep = node.dependencies[0].lock().await;
 	w_dep.resolve_predicate().unwrap();
 	assert_eq!(w_dep.url, "http://test.com");
 	assert_eq!(w_dep.method, "post");
@@ -45,7 +53,7 @@ fn it_resolves_dependency_correctly() {
 	w_dep.result = "test".to_string();
 	drop(w_dep);
 
-	node.resolve_dependencies();
+	node.resolve_dependencies().await;
 	node.resolve_predicate().unwrap();
 
 	assert_eq!(node.url, "http://example.com/test/".to_string());
@@ -88,3 +96,107 @@ fn it_resolves_headers_correctly() {
 			.unwrap()
 	);
 }
+
+#[test]
+fn it_resolves_custom_methods() {
+	let mut node = get_node("PROPFIND http://example.com".to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.method, "PROPFIND");
+
+	let mut node = get_node("propfind http://example.com".to_string());
+	assert!(node.resolve_predicate().is_err());
+}
+
+#[test]
+fn it_encodes_and_merges_query_parameters() {
+	let mut node = get_node(r#"get http://example.com/?a=1#top ?q="a b&c" ?page=2"#.to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.url, "http://example.com/?a=1&q=a%20b%26c&page=2#top");
+}
+
+#[test]
+fn it_resolves_timeout_setting() {
+	let mut node = get_node("get http://example.com @timeout=1.5s".to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.timeout, Some(Duration::from_millis(1500)));
+
+	let mut node = get_node("get http://example.com @timeout=250ms".to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.timeout, Some(Duration::from_millis(250)));
+
+	let mut node = get_node("get http://example.com @timeout=1h".to_string());
+	assert!(node.resolve_predicate().is_err());
+}
+
+#[test]
+fn it_resolves_dedupe_setting() {
+	let mut node = get_node("post http://example.com @dedupe=true".to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.dedupe, Some(true));
+
+	let mut node = get_node("get http://example.com @dedupe=yes".to_string());
+	assert!(node.resolve_predicate().is_err());
+}
+
+#[test]
+fn it_resolves_each_element() {
+	let node =
+		get_node("get http://example.com/{each get http://test.com^$[*]}/ >user".to_string());
+	assert_eq!(node.each_dependency, Some(0));
+
+	let mut element = node.resolve_each("42");
+	element.resolve_predicate().unwrap();
+	assert_eq!(element.url, "http://example.com/42/");
+	assert_eq!(element.save_as, None);
+}
+
+#[test]
+fn it_resolves_assertions() {
+	let mut node = get_node(r#"get http://example.com %status!=500 %$.id~="^[0-9]+$""#.to_string());
+	node.resolve_predicate().unwrap();
+	assert_eq!(node.assertions.len(), 2);
+	assert_eq!(node.assertions[0].to_string(), "status != 500");
+	assert!(node.assertions[1].pattern.as_ref().unwrap().is_match("42"));
+
+	let mut node = get_node("get http://example.com %body.id=1".to_string());
+	assert!(node.resolve_predicate().is_err());
+
+	let mut node = get_node(r#"get http://example.com %$.id~="(""#.to_string());
+	assert!(node.resolve_predicate().is_err());
+}
+
+#[test]
+fn it_applies_request_defaults() {
+	let mut headers = HeaderMap::new();
+	headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+	headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer default"));
+
+	let defaults = RequestDefaults {
+		base_url: Some(String::from("http://example.com/")),
+		headers,
+	};
+
+	let mut node = get_node(r#"get /users *authorization="Bearer own""#.to_string());
+	node.resolve_predicate().unwrap();
+	node.apply_defaults(&defaults).unwrap();
+	assert_eq!(node.url, "http://example.com/users");
+
+	let headers = node.headers.unwrap();
+	assert_eq!(headers.get(ACCEPT).unwrap(), "application/json");
+	assert_eq!(headers.get_all(AUTHORIZATION).iter().count(), 1);
+	assert_eq!(headers.get(AUTHORIZATION).unwrap(), "Bearer own");
+
+	let mut node = get_node("get http://test.com/users".to_string());
+	node.resolve_predicate().unwrap();
+	node.apply_defaults(&defaults).unwrap();
+	assert_eq!(node.url, "http://test.com/users");
+
+	let mut node = get_node("get /users *-accept".to_string());
+	node.resolve_predicate().unwrap();
+	node.apply_defaults(&defaults).unwrap();
+	assert!(!node.headers.unwrap().contains_key(ACCEPT));
+
+	let mut node = get_node("get /users".to_string());
+	node.resolve_predicate().unwrap();
+	assert!(node.apply_defaults(&RequestDefaults::default()).is_err());
+}
@@ -0,0 +1,341 @@
+use gluescript::{
+	lexer::tokenize, parse, parse_request, Comparison, OperatorKind, TokenKind, ValuePart,
+};
+
+const URL_WITH_OPERATOR_CHARS: &str = "get http://example.com/?a=1&b>2 >result";
+const SELECTOR_WITH_WILDCARD: &str = "get http://example.com^$.users[*].id";
+const NESTED_IN_QUOTES: &str =
+	r#"get http://example.com*authorization="Bearer {req token^$.access_token}""#;
+const RAW_BODY: &str = r#"post http://example.com ~#-{ "name": "{not nested}" }-#"#;
+const NESTED_EACH: &str =
+	"get http://example.com/users/{ each get http://example.com/users^$[*].id }";
+const MULTIPLE_EACH: &str =
+	"get http://example.com/{each get http://a.com^$[*]}/{each get http://b.com^$[*]}";
+const ASSERTIONS: &str = r#"get http://example.com %status=200 %$.name~="^B" %headers.etag"#;
+const COMMENTS: &str = "#!/usr/bin/env glue
+# first request
+get http://example.com/#top # fragment is kept
+	^$.id; # after separator
+get http://example.com/a;# right after separator
+
+get http://example.com";
+const ENV_VARIABLES: &str = r#"post ${GLUE_TEST_HOST}/users ^${GLUE_TEST_UNSET:-$.id} *authorization="Bearer ${GLUE_TEST_TOKEN}" ~#-{"token": "${GLUE_TEST_TOKEN}"}-#"#;
+const SEQUENTIAL_REQUESTS: &str = r#"get http://example.com*x-sep="a;b";
+
+req test"#;
+
+#[test]
+fn it_keeps_operator_chars_in_url() {
+	let request = parse_request(URL_WITH_OPERATOR_CHARS).unwrap();
+	assert_eq!(
+		request.url.resolve().unwrap(),
+		"http://example.com/?a=1&b>2"
+	);
+	assert_eq!(
+		request.operators[0].kind,
+		OperatorKind::SaveAs("result".to_string())
+	);
+}
+
+#[test]
+fn it_keeps_operator_chars_in_selector() {
+	let request = parse_request(SELECTOR_WITH_WILDCARD).unwrap();
+	match &request.operators[0].kind {
+		OperatorKind::Selector(value) => assert_eq!(value.resolve().unwrap(), "$.users[*].id"),
+		x => panic!("unexpected operator {:?}", x),
+	}
+}
+
+#[test]
+fn it_parses_nested_request_in_quotes() {
+	let request = parse_request(NESTED_IN_QUOTES).unwrap();
+	let value = match &request.operators[0].kind {
+		OperatorKind::Header { key, value } => {
+			assert_eq!(key, "authorization");
+			value
+		}
+		x => panic!("unexpected operator {:?}", x),
+	};
+
+	assert_eq!(value.parts[0], ValuePart::Text("Bearer ".to_string()));
+	match &value.parts[1] {
+		ValuePart::Nested { request, .. } => {
+			assert_eq!(request.method, "req");
+			assert_eq!(request.url.resolve().unwrap(), "token");
+		}
+		x => panic!("unexpected part {:?}", x),
+	}
+}
+
+#[test]
+fn it_does_not_parse_raw_body() {
+	let request = parse_request(RAW_BODY).unwrap();
+	assert_eq!(
+		request.operators[0].kind,
+		OperatorKind::RawBody(r#"{ "name": "{not nested}" }"#.to_string())
+	);
+}
+
+#[test]
+fn it_parses_sequential_requests() {
+	let requests = parse(SEQUENTIAL_REQUESTS).unwrap().requests;
+	assert_eq!(requests.len(), 2);
+	assert_eq!(requests[1].method, "req");
+	assert_eq!(
+		&SEQUENTIAL_REQUESTS[requests[1].span.start..requests[1].span.end],
+		"req test"
+	);
+}
+
+#[test]
+fn it_fails_on_unbalanced_delimiters() {
+	assert!(parse_request("get http://example.com/{get http://test.com").is_err());
+	assert!(parse_request("get http://example.com/}").is_err());
+	assert!(parse_request(r#"get http://example.com*a="b"#).is_err());
+}
+
+#[test]
+fn it_locates_errors_in_source() {
+	let source = "get http://example.com;\n\npost http://example.com\n\t~username=";
+	let error = parse(source).unwrap_err();
+
+	assert_eq!(error.line, 4);
+	assert_eq!(error.column, 2);
+	assert_eq!(error.length, 10);
+	assert_eq!(error.line_text, "\t~username=");
+	assert!(error.help.is_some());
+	assert!(error.diagnostic().contains("\t~username="));
+}
+
+#[test]
+fn it_parses_each_nested_request() {
+	let request = parse_request(NESTED_EACH).unwrap();
+	match &request.url.parts[1] {
+		ValuePart::Nested { request, each, .. } => {
+			assert!(each);
+			assert_eq!(request.method, "get");
+		}
+		x => panic!("unexpected part {:?}", x),
+	}
+
+	assert!(parse_request(MULTIPLE_EACH).is_err());
+}
+
+#[test]
+fn it_parses_assertions() {
+	let request = parse_request(ASSERTIONS).unwrap();
+	let comparisons: Vec<_> = request
+		.operators
+		.iter()
+		.map(|x| match &x.kind {
+			OperatorKind::Assert {
+				subject,
+				comparison,
+				expected,
+			} => (
+				subject.as_str(),
+				*comparison,
+				expected.as_ref().map(|x| x.resolve().unwrap()),
+			),
+			x => panic!("unexpected operator {:?}", x),
+		})
+		.collect();
+
+	assert_eq!(
+		comparisons,
+		vec![
+			("status", Comparison::Equals, Some("200".to_string())),
+			("$.name", Comparison::Matches, Some("^B".to_string())),
+			("headers.etag", Comparison::Exists, None),
+		]
+	);
+
+	assert!(parse_request("get http://example.com %=200").is_err());
+}
+
+#[test]
+fn it_ignores_comments() {
+	let requests = parse(COMMENTS).unwrap().requests;
+	assert_eq!(requests.len(), 3);
+	assert_eq!(
+		requests[0].url.resolve().unwrap(),
+		"http://example.com/#top"
+	);
+	assert_eq!(requests[0].operators.len(), 1);
+	assert_eq!(requests[1].url.resolve().unwrap(), "http://example.com/a");
+}
+
+#[test]
+fn it_interpolates_env_variables() {
+	std::env::set_var("GLUE_TEST_HOST", "http://example.com");
+	std::env::set_var("GLUE_TEST_TOKEN", "xxx");
+
+	let request = parse_request(ENV_VARIABLES).unwrap();
+	assert_eq!(request.url.resolve().unwrap(), "http://example.com/users");
+
+	let values: Vec<String> = request
+		.operators
+		.iter()
+		.map(|x| match &x.kind {
+			OperatorKind::Selector(value) => value.resolve().unwrap(),
+			OperatorKind::Header { value, .. } => value.resolve().unwrap(),
+			OperatorKind::RawBody(json) => json.clone(),
+			x => panic!("unexpected operator {:?}", x),
+		})
+		.collect();
+
+	assert_eq!(values, vec!["$.id", "Bearer xxx", r#"{"token": "xxx"}"#]);
+
+	let error = parse_request("get ${GLUE_TEST_UNSET}/users").unwrap_err();
+	assert!(error
+		.diagnostic()
+		.contains("Undefined environment variable"));
+}
+
+#[test]
+fn it_parses_directives() {
+	let script = parse(
+		"@base http://example.com/v1;\nget /users;\n@base ${GLUE_TEST_UNSET:-http://test.com}",
+	)
+	.unwrap();
+	assert_eq!(script.requests.len(), 1);
+	assert_eq!(script.directives.len(), 2);
+	assert_eq!(script.base_url(), Some("http://test.com"));
+
+	assert!(parse("@base").is_err());
+	assert!(parse("@base http://example.com/{get http://test.com}").is_err());
+	assert!(parse("@unknown http://example.com").is_err());
+	assert!(parse_request("@base http://example.com; get /users").is_err());
+}
+
+#[test]
+fn it_adds_default_headers() {
+	let script = parse(
+		"@header accept=json;\n@header authorization=\"Bearer {req token}\";\nget /users/{get /me^$.id} *accept=xml *-authorization",
+	)
+	.unwrap();
+	let headers = script.headers();
+	assert_eq!(headers.len(), 2);
+
+	let mut request = script.requests[0].clone();
+	request.add_headers(&headers);
+	assert!(request.has_header("Accept"));
+	assert_eq!(request.operators.len(), 2);
+	assert_eq!(
+		request.operators[1].kind,
+		OperatorKind::RemoveHeader("authorization".to_string())
+	);
+
+	match &request.url.parts[1] {
+		ValuePart::Nested { request, .. } => assert_eq!(request.operators.len(), 3),
+		x => panic!("unexpected part {:?}", x),
+	}
+
+	assert!(parse("@header accept").is_err());
+}
+
+#[test]
+fn it_parses_variables() {
+	let script = parse(
+		"let name = \"John Doe\";\nlet id = {get http://example.com/me^$.id};\nget http://example.com/users/$id ?$top=1 ~name=$name ^$.id",
+	)
+	.unwrap();
+	assert!(script.directives.is_empty());
+	assert_eq!(script.requests.len(), 3);
+
+	let assignment = &script.requests[0];
+	assert_eq!(assignment.method, "let");
+	assert_eq!(assignment.url.resolve().unwrap(), "John Doe");
+
+	let assignment = &script.requests[1];
+	assert_eq!(assignment.method, "let");
+	assert_eq!(
+		assignment.operators[0].kind,
+		OperatorKind::SaveAs("id".to_string())
+	);
+
+	let request = &script.requests[2];
+	match &request.url.parts[1] {
+		ValuePart::Nested { request, .. } => {
+			assert_eq!(request.method, "req");
+			assert_eq!(request.url.resolve().unwrap(), "id");
+		}
+		x => panic!("unexpected part {:?}", x),
+	}
+	match &request.operators[0].kind {
+		OperatorKind::Query { key, .. } => assert_eq!(key, "$top"),
+		x => panic!("unexpected operator {:?}", x),
+	}
+	match &request.operators[2].kind {
+		OperatorKind::Selector(x) => assert_eq!(x.resolve().unwrap(), "$.id"),
+		x => panic!("unexpected operator {:?}", x),
+	}
+
+	assert!(parse("let 1d = 1").is_err());
+	assert!(parse("let id {get http://example.com}").is_err());
+
+	// Assignments can be parsed on their own, like in glueshell.
+	let assignment = parse_request("let name = \"John Doe\"").unwrap();
+	assert_eq!(assignment.method, "let");
+	assert_eq!(assignment.url.resolve().unwrap(), "John Doe");
+}
+
+#[test]
+fn it_keeps_literal_dollars() {
+	// A `$` after a word char is not a variable.
+	let request = parse_request("get http://example.com/echo/a$b").unwrap();
+	assert_eq!(
+		request.url.resolve().unwrap(),
+		"http://example.com/echo/a$b"
+	);
+
+	// Variables at the start of a word can be escaped.
+	let request = parse_request(r"get http://example.com/price/\$USD").unwrap();
+	assert_eq!(
+		request.url.resolve().unwrap(),
+		"http://example.com/price/$USD"
+	);
+
+	// Between quotes too.
+	let request = parse_request(r#"post http://example.com ~note="cost \$USD""#).unwrap();
+	match &request.operators[0].kind {
+		OperatorKind::Body { value, .. } => assert_eq!(value.resolve().unwrap(), "cost $USD"),
+		x => panic!("unexpected operator {:?}", x),
+	}
+}
+
+#[test]
+fn it_lexes_variables_between_quotes() {
+	let tokens = tokenize(r#"*authorization="Bearer $token""#).unwrap();
+	let kinds: Vec<TokenKind> = tokens.into_iter().map(|x| x.kind).collect();
+	assert_eq!(
+		kinds[3..],
+		[
+			TokenKind::Quote,
+			TokenKind::Text(String::from("Bearer ")),
+			TokenKind::Variable(String::from("token")),
+			TokenKind::Quote,
+		]
+	);
+}
+
+#[test]
+fn it_parses_variables_between_quotes() {
+	let request =
+		parse_request(r#"post http://example.com *authorization="Bearer $token" ~note="id $id!""#)
+			.unwrap();
+
+	for (operator, name) in request.operators.iter().zip(["token", "id"]) {
+		let value = match &operator.kind {
+			OperatorKind::Header { value, .. } | OperatorKind::Body { value, .. } => value,
+			x => panic!("unexpected operator {:?}", x),
+		};
+		match &value.parts[1] {
+			ValuePart::Nested { request, .. } => {
+				assert_eq!(request.method, "req");
+				assert_eq!(request.url.resolve().unwrap(), name);
+			}
+			x => panic!("unexpected part {:?}", x),
+		}
+	}
+}
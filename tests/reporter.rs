@@ -0,0 +1,137 @@
+use gluerunner::{AssertionResult, GlueError, JUnitReporter, Reporter, TapReporter, TestResult};
+use gluescript::{Assertion, Comparison};
+use std::{
+	io::{self, Write},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+/// A writer keeping everything written in memory, readable after
+/// being moved into a reporter.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.lock().unwrap().write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Buffer {
+	fn contents(&self) -> String {
+		String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+	}
+}
+
+fn record_all(reporter: &mut dyn Reporter) {
+	reporter.record(
+		"users.glue",
+		&TestResult {
+			request: String::from("get http://example.com/users\n%status=200"),
+			assertions: vec![],
+			duration: Duration::from_millis(120),
+			status: Some(200),
+			error: None,
+		},
+	);
+	reporter.record(
+		"users.glue",
+		&TestResult {
+			request: String::from("get http://example.com/users/<1>"),
+			assertions: vec![],
+			duration: Duration::from_millis(30),
+			status: None,
+			error: Some(GlueError::Timeout(String::from("get http://example.com"))),
+		},
+	);
+	reporter.finish().unwrap();
+}
+
+#[test]
+fn it_writes_junit_reports() {
+	let buffer = Buffer::default();
+	record_all(&mut JUnitReporter::new(Box::new(buffer.clone())));
+	let report = buffer.contents();
+
+	assert!(report.contains(r#"<testsuites name="glue" tests="2" failures="0" errors="1""#));
+	assert!(report.contains(
+		r#"<testcase name="get http://example.com/users %status=200" classname="users.glue" time="0.120">"#
+	));
+	assert!(report.contains(r#"name="get http://example.com/users/&lt;1&gt;""#));
+	assert!(report.contains("<error message="));
+}
+
+#[test]
+fn it_writes_tap_reports() {
+	let buffer = Buffer::default();
+	record_all(&mut TapReporter::new(Box::new(buffer.clone())));
+	let report = buffer.contents();
+
+	assert!(report.starts_with("TAP version 13\n1..2\n# users.glue\n"));
+	assert!(report.contains("ok 1 - get http://example.com/users %status=200\n"));
+	assert!(report.contains("not ok 2 - get http://example.com/users/<1>\n"));
+	assert!(report.contains("  status: 200\n"));
+}
+
+/// Record a request with a passed and a failed assertion.
+fn record_assertions(reporter: &mut dyn Reporter) {
+	let assertion = |expected: &str, failure: Option<&str>| AssertionResult {
+		request: String::from("get http://example.com"),
+		assertion: Assertion {
+			subject: String::from("status"),
+			comparison: Comparison::Equals,
+			expected: String::from(expected),
+			pattern: None,
+		},
+		failure: failure.map(String::from),
+	};
+
+	let failed = assertion("201", Some("got 200"));
+	reporter.record(
+		"users.glue",
+		&TestResult {
+			request: String::from("get http://example.com %status=200 %status=201"),
+			assertions: vec![assertion("200", None), failed.clone()],
+			duration: Duration::from_millis(10),
+			status: Some(200),
+			error: Some(GlueError::Assertion(vec![failed])),
+		},
+	);
+	reporter.finish().unwrap();
+}
+
+#[test]
+fn it_reports_each_assertion() {
+	let buffer = Buffer::default();
+	record_assertions(&mut JUnitReporter::new(Box::new(buffer.clone())));
+	let report = buffer.contents();
+
+	assert!(report.contains("<failure message="));
+	assert!(report.contains(
+		"<system-out>status 200\n`status = 200` passed\n`status = 201` failed: got 200</system-out>"
+	));
+
+	let buffer = Buffer::default();
+	record_assertions(&mut TapReporter::new(Box::new(buffer.clone())));
+	let report = buffer.contents();
+
+	assert!(report.contains(
+		"    # Subtest: get http://example.com %status=200 %status=201\n    ok 1 - status = 200\n    not ok 2 - status = 201\n      ---\n      message: |\n        got 200\n      ...\n    1..2\nnot ok 1 - "
+	));
+}
+
+#[test]
+fn it_writes_empty_reports() {
+	let buffer = Buffer::default();
+	JUnitReporter::new(Box::new(buffer.clone()))
+		.finish()
+		.unwrap();
+
+	assert!(buffer
+		.contents()
+		.contains(r#"<testsuites name="glue" tests="0" failures="0" errors="0" time="0.000">"#));
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use gluescript::Response;
+use reqwest::{header::LOCATION, StatusCode};
+use std::time::Duration;
+
+fn get_response() -> Response {
+	Response {
+		duration: Duration::from_millis(42),
+		url: String::from("http://example.com/users"),
+		..common::get_response(StatusCode::CREATED, &[(LOCATION, "/users/1")], "{}")
+	}
+}
+
+#[test]
+fn it_selects_response_fields() {
+	let response = get_response();
+	assert_eq!(response.field("status").unwrap(), "201");
+	assert_eq!(response.field("duration").unwrap(), "42");
+	assert_eq!(response.field("url").unwrap(), "http://example.com/users");
+	assert_eq!(response.field("headers.Location").unwrap(), "/users/1");
+	assert!(response.field("headers.etag").is_none());
+	assert!(response.field("body").is_none());
+}
+
+#[test]
+fn it_renders_response_head() {
+	assert_eq!(
+		get_response().head(),
+		"HTTP/1.1 201 Created\nlocation: /users/1"
+	);
+}
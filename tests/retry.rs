@@ -0,0 +1,56 @@
+mod common;
+
+use common::{get_response, Server};
+use gluerunner::{heap, GlueError, RetryPolicy, Runner};
+use reqwest::Client;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use std::time::Duration;
+
+#[test]
+fn it_retries_transient_statuses_only() {
+	let policy = RetryPolicy::new(3);
+	assert!(policy.retries_response(&get_response(StatusCode::SERVICE_UNAVAILABLE, &[], "")));
+	assert!(policy.retries_response(&get_response(StatusCode::TOO_MANY_REQUESTS, &[], "")));
+	assert!(!policy.retries_response(&get_response(StatusCode::INTERNAL_SERVER_ERROR, &[], "")));
+	assert!(!policy.retries_response(&get_response(StatusCode::OK, &[], "")));
+}
+
+#[test]
+fn it_backs_off_exponentially_with_jitter() {
+	let policy = RetryPolicy::new(3);
+
+	for attempt in 1..=3 {
+		let backoff = Duration::from_millis(200 * 2u64.pow(attempt - 1));
+		let delay = policy.delay(attempt, None);
+		assert!(delay >= backoff / 2 && delay <= backoff);
+	}
+
+	// Backoff never exceeds its maximum.
+	assert!(policy.delay(30, None) <= Duration::from_secs(10));
+}
+
+#[test]
+fn it_honors_retry_after() {
+	let policy = RetryPolicy::new(1);
+	let response = get_response(StatusCode::SERVICE_UNAVAILABLE, &[(RETRY_AFTER, "3")], "");
+	assert_eq!(policy.delay(1, Some(&response)), Duration::from_secs(3));
+}
+
+#[test]
+fn it_retries_transient_errors_only() {
+	let policy = RetryPolicy::new(3);
+	assert!(policy.retries_error(&GlueError::Timeout(String::from("get x"))));
+
+	let error = Client::new().get("not a url").build().unwrap_err();
+	assert!(!policy.retries_error(&GlueError::Http(error)));
+}
+
+#[tokio::test]
+async fn it_sends_requests_failing_for_good_once() {
+	let server = Server::start();
+	let command = format!("get {}/malformed @retries=3", server.url);
+
+	let mut runner = Runner::from_string(&command, heap(), Client::new(), false).unwrap();
+	assert!(matches!(runner.execute().await, Err(GlueError::Http(_))));
+	assert_eq!(server.count("GET /malformed"), 1);
+}
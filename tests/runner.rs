@@ -0,0 +1,318 @@
+mod common;
+
+use common::Server;
+use gluerunner::{heap, ErrorPolicy, GlueError, Runner, Stack};
+use reqwest::Client;
+
+#[test]
+fn it_reports_parse_errors_as_typed_errors() {
+	let mut stack = Stack::new();
+	let err = stack
+		.push_runner_from_string("get http://example.com/{get http://test.com", false)
+		.unwrap_err();
+
+	assert!(matches!(err, GlueError::Parse(_)));
+	assert!(err.diagnostic().contains("Unclosed nested request"));
+}
+
+#[test]
+fn it_reports_missing_files_as_io_errors() {
+	let mut stack = Stack::new();
+	let err = stack
+		.push_from_file(String::from("./does-not-exist.glue"), false)
+		.unwrap_err();
+
+	assert!(matches!(err, GlueError::Io(_)));
+}
+
+#[test]
+fn it_builds_the_dependency_graph() {
+	let runner = Runner::from_string(
+		"get http://a.com/{get http://b.com/{get http://c.com}}/{get http://d.com}",
+		heap(),
+		Client::new(),
+		false,
+	)
+	.unwrap();
+
+	let graph = &runner.graph;
+	assert_eq!(graph.nodes.len(), 4);
+	assert_eq!(
+		graph.dependencies,
+		vec![vec![1, 3], vec![2], vec![], vec![]]
+	);
+	assert_eq!(graph.dependents, vec![vec![], vec![0], vec![1], vec![0]]);
+	assert_eq!(graph.leaves(), vec![2, 3]);
+}
+
+#[test]
+fn it_finds_heap_variables_of_runners() {
+	let runner = Runner::from_string(
+		"get http://a.com/{req token^$.id} >user",
+		heap(),
+		Client::new(),
+		false,
+	)
+	.unwrap();
+
+	assert_eq!(runner.reads, vec!["token"]);
+	assert_eq!(runner.saves, vec!["user"]);
+}
+
+#[tokio::test]
+async fn it_skips_runners_using_variables_of_failed_ones() {
+	let mut stack = Stack::new();
+	stack.set_error_policy(ErrorPolicy::Skip);
+	stack
+		.push_runner_from_string("req missing >a", false)
+		.unwrap();
+	stack.push_runner_from_string("req a", false).unwrap();
+
+	let results = stack.execute_tests().await;
+	assert!(matches!(
+		results[0].error,
+		Some(GlueError::UnresolvedVariable(_))
+	));
+	assert!(matches!(
+		&results[1].error,
+		Some(GlueError::Skipped { variable, .. }) if variable == "a"
+	));
+}
+
+/// Execute `command` in a runner of its own, returning its result.
+async fn execute(command: &str) -> String {
+	let mut runner = Runner::from_string(command, heap(), Client::new(), false).unwrap();
+	runner.execute().await.unwrap();
+	runner.result.unwrap()
+}
+
+#[tokio::test]
+async fn it_sends_identical_requests_once() {
+	let server = Server::start();
+	let url = &server.url;
+
+	execute(&format!(
+		"get {url}/echo ?a={{get {url}/login^$.token}} ?b={{get {url}/login^$.token}}"
+	))
+	.await;
+	assert_eq!(server.count("GET /login"), 1);
+
+	// Requests with different headers or bodies are not identical.
+	execute(&format!(
+		"get {url}/echo ?a={{get {url}/items/1 *x-a=1 ^$.id}} ?b={{get {url}/items/1 *x-a=2 ^$.id}}"
+	))
+	.await;
+	assert_eq!(server.count("GET /items/1"), 2);
+
+	execute(&format!(
+		"get {url}/echo ?a={{post {url}/items/2 @dedupe=true ~a=1 ^$.id}} ?b={{post {url}/items/2 @dedupe=true ~a=2 ^$.id}}"
+	))
+	.await;
+	assert_eq!(server.count("POST /items/2"), 2);
+
+	// Requests with side effects are only shared if asked to.
+	execute(&format!(
+		"get {url}/echo ?a={{post {url}/items/3 ^$.id}} ?b={{post {url}/items/3 ^$.id}}"
+	))
+	.await;
+	assert_eq!(server.count("POST /items/3"), 2);
+
+	execute(&format!(
+		"get {url}/echo ?a={{post {url}/items/4 @dedupe=true ^$.id}} ?b={{post {url}/items/4 @dedupe=true ^$.id}}"
+	))
+	.await;
+	assert_eq!(server.count("POST /items/4"), 1);
+}
+
+#[tokio::test]
+async fn it_sends_a_request_for_each_element() {
+	let server = Server::start();
+	let url = &server.url;
+
+	let result = execute(&format!(
+		"get {url}/items/{{each get {url}/list/3^$[*]}}^$.name"
+	))
+	.await;
+	let names: Vec<String> = serde_json::from_str(&result).unwrap();
+	assert_eq!(names, vec!["item 1", "item 2", "item 3"]);
+
+	// Nothing is sent for an empty list.
+	let result = execute(&format!("get {url}/items/{{each get {url}/list/0^$[*]}}")).await;
+	assert_eq!(
+		serde_json::from_str::<Vec<String>>(&result).unwrap(),
+		Vec::<String>::new()
+	);
+	assert_eq!(server.count("GET /items/1"), 1);
+}
+
+#[tokio::test]
+async fn it_assigns_variables_in_order() {
+	let server = Server::start();
+	let url = &server.url;
+
+	let mut stack = Stack::new();
+	stack
+		.push_from_script(
+			format!(
+				"let x = \"a\"; get {url}/echo/$x ^$.path >first; let x = \"b\"; get {url}/echo/$x ^$.path >second"
+			),
+			false,
+		)
+		.unwrap();
+
+	let results = stack.execute_tests().await;
+	assert!(results.iter().all(|x| x.passed()));
+
+	let heap = stack.heap().lock().unwrap();
+	let path = |name: &str| serde_json::from_str::<Vec<String>>(&heap[name]).unwrap();
+	assert_eq!(path("first"), vec!["/echo/a"]);
+	assert_eq!(path("second"), vec!["/echo/b"]);
+}
+
+#[tokio::test]
+async fn it_sends_default_headers_using_assigned_variables() {
+	let server = Server::start();
+	let url = &server.url;
+
+	let mut stack = Stack::new();
+	stack
+		.push_from_script(
+			format!(
+				"@header authorization=\"Bearer $token\"; let token = {{get {url}/login *-authorization ^$.token}}; get {url}/echo ^$.headers.authorization >auth"
+			),
+			false,
+		)
+		.unwrap();
+
+	let results = stack.execute_tests().await;
+	assert!(results.iter().all(|x| x.passed()));
+
+	let heap = stack.heap().lock().unwrap();
+	let auth: Vec<String> = serde_json::from_str(&heap["auth"]).unwrap();
+	assert_eq!(auth, vec!["Bearer t0k"]);
+}
+
+#[tokio::test]
+async fn it_assigns_variables_in_single_requests() {
+	let mut stack = Stack::new();
+	stack
+		.push_runner_from_string("let x = \"a\"", false)
+		.unwrap();
+	stack.push_runner_from_string("req x", false).unwrap();
+
+	stack.execute_all().await.unwrap();
+	assert_eq!(stack.current().unwrap().result.as_deref(), Some("a"));
+}
+
+#[tokio::test]
+async fn it_reports_assignments_only_if_failed() {
+	let mut stack = Stack::new();
+	stack
+		.push_from_script(
+			String::from("let a = \"x\"; let b = {req missing}; req a"),
+			false,
+		)
+		.unwrap();
+
+	let results = stack.execute_tests().await;
+	assert_eq!(results.len(), 2);
+	assert!(results[0].request.starts_with("let b"));
+	assert!(matches!(
+		results[0].error,
+		Some(GlueError::UnresolvedVariable(_))
+	));
+	assert!(results[1].passed());
+}
+
+#[tokio::test]
+async fn it_assigns_selected_numbers() {
+	let server = Server::start();
+	let url = &server.url;
+
+	let mut stack = Stack::new();
+	stack
+		.push_from_script(
+			format!("let n = {{get {url}/list/3 ^$[1]}}; get {url}/items/$n ^$.name >name"),
+			false,
+		)
+		.unwrap();
+
+	let results = stack.execute_tests().await;
+	assert!(results.iter().all(|x| x.passed()));
+
+	let heap = stack.heap().lock().unwrap();
+	assert_eq!(heap["n"], "2");
+	assert_eq!(heap["name"], "[\n  \"item 2\"\n]");
+}
+
+#[test]
+fn it_reports_unknown_variables_as_parse_errors() {
+	let mut stack = Stack::new();
+
+	let error = stack
+		.push_from_script(
+			String::from("get http://example.com >a; get http://example.com/$b"),
+			false,
+		)
+		.unwrap_err();
+	match error {
+		GlueError::Parse(x) => assert_eq!(x.span.start, 50),
+		x => panic!("unexpected error {:?}", x),
+	}
+
+	// Nothing is pushed from a script with unknown variables.
+	stack
+		.push_from_script(String::from("get http://example.com/$a"), false)
+		.unwrap_err();
+
+	stack
+		.push_runner_from_string("get http://example.com/$metadata", false)
+		.unwrap_err();
+
+	// Variables in the heap, saved by other runners or escaped are known.
+	stack.set_variable("a", "1");
+	stack
+		.push_runner_from_string("get http://example.com/$a >b", false)
+		.unwrap();
+	stack
+		.push_runner_from_string("get http://example.com/$b/\\$metadata", false)
+		.unwrap();
+}
+
+#[tokio::test]
+async fn it_fails_requests_depending_on_timed_out_ones() {
+	let server = Server::start();
+	let url = &server.url;
+
+	// Nested requests depending on a timed out one are never sent.
+	let mut runner = Runner::from_string(
+		&format!("get {url}/echo/{{get {url}/delay/500 @timeout=50ms ^$.delay}}"),
+		heap(),
+		Client::new(),
+		false,
+	)
+	.unwrap();
+	match runner.execute().await {
+		Err(GlueError::Timeout(x)) => assert!(x.starts_with(&format!("get {url}/delay/500"))),
+		x => panic!("unexpected result {:?}", x),
+	}
+	assert_eq!(server.count("GET /delay/500"), 1);
+	assert_eq!(server.count("GET /echo/500"), 0);
+
+	// So are requests reading a variable it would have saved.
+	let mut stack = Stack::new();
+	stack.set_error_policy(ErrorPolicy::Skip);
+	stack
+		.push_from_script(
+			format!("get {url}/delay/500 @timeout=50ms ^$.delay >slow; get {url}/echo/$slow"),
+			false,
+		)
+		.unwrap();
+
+	let results = stack.execute_tests().await;
+	assert!(matches!(results[0].error, Some(GlueError::Timeout(_))));
+	assert!(matches!(
+		&results[1].error,
+		Some(GlueError::Skipped { variable, .. }) if variable == "slow"
+	));
+}
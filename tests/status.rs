@@ -0,0 +1,22 @@
+use gluescript::StatusPolicy;
+use reqwest::StatusCode;
+
+#[test]
+fn it_accepts_only_successes_by_default() {
+	let policy = StatusPolicy::default();
+	assert!(policy.accepts(StatusCode::OK));
+	assert!(policy.accepts(StatusCode::FOUND));
+	assert!(!policy.accepts(StatusCode::NOT_FOUND));
+	assert!(!policy.accepts(StatusCode::INTERNAL_SERVER_ERROR));
+}
+
+#[test]
+fn it_accepts_codes_and_classes() {
+	let policy = StatusPolicy::parse("404, 5XX").unwrap();
+	assert!(policy.accepts(StatusCode::NOT_FOUND));
+	assert!(policy.accepts(StatusCode::BAD_GATEWAY));
+	assert!(!policy.accepts(StatusCode::UNAUTHORIZED));
+
+	assert_eq!(StatusPolicy::parse("4x").unwrap_err(), "4x");
+	assert_eq!(StatusPolicy::parse("700").unwrap_err(), "700");
+}
@@ -0,0 +1,65 @@
+use gluerunner::Throttle;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+#[test]
+fn it_parses_rate_limits() {
+	assert_eq!(
+		Throttle::parse_rate("api.example.com=5"),
+		Some((String::from("api.example.com"), 5.0))
+	);
+	assert_eq!(Throttle::parse_rate("0.5"), Some((String::from("*"), 0.5)));
+	assert_eq!(Throttle::parse_rate("api.example.com=0"), None);
+	assert_eq!(Throttle::parse_rate("=5"), None);
+	assert_eq!(Throttle::parse_rate("1e-300"), None);
+	assert_eq!(Throttle::parse_rate("1e300"), None);
+	assert_eq!(Throttle::parse_rate("inf"), None);
+	assert_eq!(Throttle::parse_rate("fast"), None);
+}
+
+#[tokio::test]
+async fn it_keeps_rates_within_bounds() {
+	let mut throttle = Throttle::default();
+	throttle.set_rate("a.com", 1e-300);
+	throttle.set_rate("b.com", 1e300);
+
+	// Only the wait for the second request is affected by the rate.
+	throttle.acquire(Some("a.com")).await;
+	let waiting = timeout(Duration::from_millis(50), throttle.acquire(Some("a.com"))).await;
+	assert!(waiting.is_err());
+
+	for _ in 0..3 {
+		throttle.acquire(Some("b.com")).await;
+	}
+}
+
+#[tokio::test]
+async fn it_limits_requests_in_flight() {
+	let mut throttle = Throttle::default();
+	throttle.set_concurrency(1);
+
+	let permit = throttle.acquire(None).await;
+	let waiting = timeout(Duration::from_millis(50), throttle.acquire(None)).await;
+	assert!(waiting.is_err());
+
+	drop(permit);
+	let waiting = timeout(Duration::from_millis(50), throttle.acquire(None)).await;
+	assert!(waiting.is_ok());
+}
+
+#[tokio::test]
+async fn it_spaces_requests_to_the_same_host() {
+	let mut throttle = Throttle::default();
+	throttle.set_rate("a.com", 20.0);
+
+	let start = Instant::now();
+	for _ in 0..3 {
+		throttle.acquire(Some("a.com")).await;
+	}
+	assert!(start.elapsed() >= Duration::from_millis(100));
+
+	// Other hosts are not limited.
+	let start = Instant::now();
+	throttle.acquire(Some("b.com")).await;
+	assert!(start.elapsed() < Duration::from_millis(50));
+}